@@ -0,0 +1,58 @@
+use clap::Parser;
+use osm_test::geometry::{Bathymetry, Bridge};
+use osm_test::routing::Graph;
+use osm_test::spatial_graph::{edge_clearances, GraphFile};
+use osm_test::Result;
+
+/// Converts a .fmi file into a binary graph file and back. The direction is given by the format
+/// of the input. With a bathymetry grid or bridges, the clearance of every edge is computed
+/// before writing, so routes can avoid the edges a vessel does not fit through.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// Path of the .fmi or binary graph file to read
+    #[arg(short, long)]
+    input: String,
+    /// Path of the file to write
+    #[arg(short, long)]
+    output: String,
+    /// Path of an ESRI ASCII grid of elevations for the depth along the edges
+    #[arg(long)]
+    bathymetry: Option<String>,
+    /// Path of a .geojson file of bridges, LineStrings with a "clearance" property in meters
+    #[arg(long)]
+    bridges: Option<String>,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let is_graph_file = GraphFile::is_graph_file(args.input.as_str());
+    let mut graph = if is_graph_file {
+        GraphFile::open(args.input.as_str())?.into_graph()
+    } else {
+        Graph::from_file(args.input.as_str())?
+    };
+
+    if args.bathymetry.is_some() || args.bridges.is_some() {
+        let bathymetry = args
+            .bathymetry
+            .as_deref()
+            .map(Bathymetry::from_file)
+            .transpose()?;
+        let bridges = match args.bridges.as_deref() {
+            Some(path) => Bridge::from_geojson_file(path)?,
+            None => Vec::new(),
+        };
+        graph.clearances = edge_clearances(&graph, bathymetry.as_ref(), &bridges).into();
+    }
+
+    if is_graph_file {
+        println!("converting binary graph file to .fmi file");
+        graph.to_file(args.output.as_str());
+    } else {
+        println!("converting .fmi file to binary graph file");
+        GraphFile::write(args.output.as_str(), &graph);
+    }
+    Ok(())
+}
@@ -0,0 +1,27 @@
+use clap::Parser;
+use osm_test::routing::{Graph, Landmarks};
+use osm_test::Result;
+
+/// Selects landmarks and precomputes their cost tables for ALT queries
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// Path of .fmi file
+    #[arg(short, long)]
+    fmi_path: String,
+    /// Number of landmarks to select
+    #[arg(short, long, default_value_t = 16)]
+    num_landmarks: usize,
+    /// Path of the .landmarks output file
+    #[arg(short, long)]
+    output: String,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let graph = Graph::from_file(args.fmi_path.as_str())?;
+    let landmarks = Landmarks::farthest(&graph, args.num_landmarks);
+    landmarks.to_file(args.output.as_str());
+    Ok(())
+}
@@ -1,5 +1,6 @@
 use clap::Parser;
 use osm_test::geometry::Planet;
+use osm_test::Result;
 
 /// Parse parameters for OSM converter
 #[derive(Parser, Debug)]
@@ -13,9 +14,10 @@ struct Args {
     output: String,
 }
 
-fn main() {
+fn main() -> Result<()> {
     let args = Args::parse();
 
-    let planet = Planet::from_osm_file(args.input.as_str());
-    planet.to_geojson_file(args.output.as_str())
+    let planet = Planet::from_osm_file(args.input.as_str())?;
+    planet.to_geojson_file(args.output.as_str());
+    Ok(())
 }
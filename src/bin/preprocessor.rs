@@ -1,5 +1,6 @@
 use clap::Parser;
 use osm_test::geometry::Planet;
+use osm_test::{Error, Result};
 
 use osm_test::spatial_graph::generate_network;
 
@@ -13,9 +14,15 @@ struct Args {
     /// Path of .fmi file
     #[arg(short, long)]
     num_nodes: u32,
+    /// Points south of this latitude are left out, e.g. the permanent ice of the Antarctic
+    #[arg(long, default_value_t = -82.0, allow_hyphen_values = true)]
+    min_latitude: f64,
     /// Path of .fmi file
     #[arg(short, long)]
     output_network: String,
+    /// Path of the binary graph file, written in addition to the .fmi file
+    #[arg(long)]
+    output_binary: Option<String>,
     /// Path of .fmi file
     #[arg(short, long)]
     output_geojson: String,
@@ -24,16 +31,25 @@ struct Args {
     output_image: String,
 }
 
-fn main() {
+fn main() -> Result<()> {
     let args = Args::parse();
+    if !(-90.0..90.0).contains(&args.min_latitude) {
+        return Err(Error::InvalidArgument(format!(
+            "the minimum latitude of {} is not between -90 and 90",
+            args.min_latitude
+        )));
+    }
 
-    let planet = Planet::from_geojson_file(args.input.as_str()).unwrap();
+    let planet = Planet::from_geojson_file(args.input.as_str())?;
 
     generate_network(
         args.num_nodes,
+        args.min_latitude,
         &planet,
         args.output_network.as_str(),
+        args.output_binary.as_deref(),
         args.output_geojson.as_str(),
         args.output_image.as_str(),
     );
+    Ok(())
 }
@@ -1,14 +1,39 @@
+use std::cell::RefCell;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::ops::Range;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use osm_test::geometry::Linestring;
-use osm_test::geometry::Planet;
-use osm_test::routing::get_route;
+use geojson::{Feature, FeatureCollection, Geometry, JsonObject};
+use osm_test::geometry::{
+    Linestring, Planet, Point, Polygon, VectorField, METERS_PER_NAUTICAL_MILE,
+};
+use osm_test::routing::order_stops;
+use osm_test::routing::AlternativeRoutes;
+use osm_test::routing::AvoidAreas;
 use osm_test::routing::Dijkstra;
+use osm_test::routing::DijkstraWorkspace;
+use osm_test::routing::DistanceTable;
+use osm_test::routing::Edge;
+use osm_test::routing::EmissionZones;
 use osm_test::routing::Graph;
-use osm_test::spatial_graph::Fmi;
+use osm_test::routing::Isochrone;
+use osm_test::routing::Route;
+use osm_test::routing::SailingTime;
+use osm_test::routing::Vessel;
+use osm_test::routing::{month_of, IceClass, IceLimit, IceZones};
+use osm_test::routing::{Canal, Canals};
+use osm_test::spatial_graph::{GraphFile, VirtualNode};
+use osm_test::spatial_partition::PolygonSpatialPartition;
+use osm_test::{Error, Result};
 use serde_derive::{Deserialize, Serialize};
-use warp::{http::Response, Filter};
+use serde_json::json;
+use warp::body::BodyDeserializeError;
+use warp::http::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
+use warp::http::{Response, StatusCode};
+use warp::reject::{MethodNotAllowed, UnsupportedMediaType};
+use warp::{Filter, Rejection, Reply};
 
 use clap::Parser;
 
@@ -16,74 +41,1156 @@ use clap::Parser;
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    /// Path of .fmi file
+    /// Path of .fmi file or binary graph file
     #[arg(short, long)]
     fmi_path: String,
+    /// Path of the planet .geojson file, used to connect positions to nearby nodes without
+    /// crossing land. Without it, positions are connected to the nearest node only.
+    #[arg(short, long)]
+    planet_path: Option<String>,
+    /// Path of a .geojson file of polygons, e.g. high risk or protected areas, that all routes
+    /// keep out of
+    #[arg(short, long)]
+    avoid_path: Option<String>,
+    /// Path of a .geojson file of emission control areas, to report and weight the distance
+    /// inside them
+    #[arg(short, long)]
+    eca_path: Option<String>,
+    /// Path of an ESRI ASCII grid of ocean currents over time, see `VectorField::from_file`, for
+    /// the fastest routes and the estimated times of arrival
+    #[arg(short, long)]
+    currents_path: Option<String>,
+    /// Path of an ESRI ASCII grid of winds over time, whose drift is used like the currents
+    #[arg(short, long)]
+    wind_path: Option<String>,
+    /// Path of a .geojson file of ice limits, polygons with the `months` they are ice covered
+    /// and the `ice_class` that may enter them, see `IceLimit::from_geojson_file`
+    #[arg(short, long)]
+    ice_path: Option<String>,
+    /// Path of a .geojson file of canals that are added to the graph, LineStrings with a `name`
+    /// and `transit_hours`, see `Canal::from_geojson_file`. The ends of the canals are linked to
+    /// the graph clear of the land of the planet file, if it is given
+    #[arg(long)]
+    canals_path: Option<String>,
     /// The address to bind to
     #[arg(short, long, default_value_t = String::from("127.0.0.1:3030"))]
     bind: String,
 }
 
+/// The number of nearby nodes a requested position is connected to.
+const NUM_CONNECTIONS: usize = 8;
+
+/// Meters the waypoints of an exported route may be off the route, if the request gives none.
+const DEFAULT_TOLERANCE: f64 = 100.0;
+
+/// The maximal number of alternative routes of a request.
+const MAX_ALTERNATIVES: usize = 10;
+
+/// The maximal depth of the cells of an isochrone. Cells at depth 20 are about 10 m wide, deeper
+/// cells only cost time.
+const MAX_ISOCHRONE_DEPTH: usize = 20;
+
+thread_local! {
+    /// Search state of the queries on this worker thread, allocated on the first query.
+    static WORKSPACE: RefCell<Option<DijkstraWorkspace>> = const { RefCell::new(None) };
+}
+
 #[derive(Deserialize, Serialize)]
 struct RouteRequest {
     from: (f64, f64), // lon, lat
     to: (f64, f64),   // lon, lat
+    #[serde(default)]
+    via: Vec<(f64, f64)>, // lon, lat, visited in order between from and to
+    #[serde(default)]
+    smooth: bool, // straightens the route where it is clear of land, needs the planet
+    knots: Option<f64>, // speed to estimate the duration with
+    #[serde(default)]
+    format: Format,
+    name: Option<String>, // name of the route in the exported file, "Route" if not given
+    tolerance: Option<f64>, // meters the exported waypoints may be off the route
+    #[serde(default)]
+    draft: f64, // meters below the water, edges with less depth are avoided
+    #[serde(default)]
+    air_draft: f64, // meters above the water, edges under lower bridges are avoided
+    #[serde(default)]
+    avoid: Vec<Vec<(f64, f64)>>, // lon, lat, outlines of areas to keep out of
+    avoid_penalty: Option<f64>, // cost factor of edges in the areas, which are blocked if not given
+    eca_weight: Option<f64>, // extra cost per meter inside emission control areas
+    #[serde(default)]
+    fastest: bool, // minimizes the time at the speed with the currents and wind, needs knots
+    departure: Option<f64>, // unix seconds, now if not given, its month selects the ice limits
+    ice_class: Option<String>, // e.g. "PC6" or "1A Super", ice is closed to vessels without one
+    ice_penalty: Option<f64>, // cost factor of edges in ice the vessel may enter, 1 if not given
+    #[serde(default)]
+    closed_canals: Vec<String>, // names of the canals closed for this route
+}
+
+/// The format of a route response. GPX, KML and RTZ files hold the route as thinned, named
+/// waypoints for chart plotters and ECDIS, GeoJSON holds the full geometry and `RouteProperties`.
+#[derive(Clone, Copy, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Format {
+    #[default]
+    Geojson,
+    Gpx,
+    Kml,
+    Rtz,
+}
+
+/// The properties of a route feature.
+#[derive(Deserialize, Serialize)]
+struct RouteProperties {
+    found: bool,               // false if no route exists, the feature has no geometry then
+    cost: Option<u32>,         // None if no route exists
+    distance: f64,             // length of the geometry in meters
+    nautical_miles: f64,       // length of the geometry in nautical miles
+    duration: Option<f64>,     // hours at the requested speed, if a speed is given
+    eca_distance: Option<f64>, // meters inside emission control areas, if they are loaded
+    from: Option<Snap>,        // the node the route starts at, for routes between positions
+    to: Option<Snap>,          // the node the route ends at, for routes between positions
+    legs: Vec<LegProperties>,  // in order from start to end
+    took_ms: u64,              // time to compute the route
+}
+
+/// The node a requested position is connected to.
+#[derive(Deserialize, Serialize)]
+struct Snap {
+    node: u32,
+    position: (f64, f64), // lon, lat of the node
+    distance: u32,        // meters between the requested position and the node
+}
+
+#[derive(Deserialize, Serialize)]
+struct LegProperties {
+    from: u32,
+    to: u32,
+    cost: u32,
+    distance: f64,
+    nautical_miles: f64,
+    duration: Option<f64>,
+    eca_distance: Option<f64>,
+    geometry: Option<Geometry>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct TableRequest {
+    sources: Vec<(f64, f64)>, // lon, lat
+    targets: Vec<(f64, f64)>, // lon, lat
+}
+
+#[derive(Deserialize, Serialize)]
+struct TableResponse {
+    sources: Vec<u32>,
+    targets: Vec<u32>,
+    costs: Vec<Vec<Option<u32>>>, // None if the target is not reachable
+}
+
+#[derive(Deserialize, Serialize)]
+struct VoyageRequest {
+    stops: Vec<(f64, f64)>, // lon, lat
+    start: Option<usize>,   // index of the stop to begin with
+    end: Option<usize>,     // index of the stop to end with
+    knots: Option<f64>,     // speed to estimate the duration with
+    #[serde(default)]
+    format: Format,
+    name: Option<String>, // name of the voyage in the exported file, "Route" if not given
+    tolerance: Option<f64>, // meters the exported waypoints may be off the route
+}
+
+#[derive(Deserialize, Serialize)]
+struct AlternativesRequest {
+    from: (f64, f64),     // lon, lat
+    to: (f64, f64),       // lon, lat
+    count: Option<usize>, // maximal number of routes, 3 if not given
+    knots: Option<f64>,   // speed to estimate the duration with
+}
+
+#[derive(Deserialize, Serialize)]
+struct IsochroneRequest {
+    from: (f64, f64),            // lon, lat
+    nautical_miles: Option<f64>, // maximal distance, or given by hours and knots
+    hours: Option<f64>,
+    knots: Option<f64>,
+    depth: Option<usize>, // depth of the tiling cells, 10 if not given
+}
+
+/// Routes along the waypoints with the workspace of the current worker thread.
+fn route_via(graph: &Graph, waypoints: &[u32]) -> Option<Vec<Route>> {
+    let dijkstra = Dijkstra::new(graph);
+    WORKSPACE.with(|workspace| {
+        let mut workspace = workspace.borrow_mut();
+        let workspace = workspace.get_or_insert_with(|| DijkstraWorkspace::new(graph.nodes.len()));
+        dijkstra.route_via(workspace, waypoints)
+    })
+}
+
+/// The costs of a route request: the meters of the edges, or the seconds to sail them for the
+/// fastest route. Edges the vessel does not fit through are blocked, edges in the avoided areas
+/// and in ice are blocked or penalized and the meters inside emission control areas are added
+/// with their weight.
+struct Costs<'a> {
+    vessel: Vessel,
+    avoid_areas: AvoidAreas,
+    emission_zones: Option<(&'a EmissionZones, f64)>,
+    /// The ice zones, the ice class of the vessel and the penalty in ice it may enter.
+    ice: Option<(&'a IceZones, Option<IceClass>, f64)>,
+    /// The canals and the indices of those closed for this route.
+    canals: Option<(&'a Canals, Vec<usize>)>,
+    /// The sailing time for the fastest route.
+    fastest: Option<SailingTime<'a>>,
+    /// Unix time of departure in seconds.
+    departure: f64,
+}
+
+impl Costs<'_> {
+    /// Returns the cost of the edge, given the cost at its source since the departure.
+    fn weight(&self, graph: &Graph, edge_id: u32, edge: &Edge, elapsed: u32) -> Option<u32> {
+        if !self.vessel.fits(&graph.clearances[edge_id as usize]) {
+            return None;
+        }
+        // the fastest route knows when it reaches the edge, others take the time of departure
+        let (cost, time) = match &self.fastest {
+            Some(sailing_time) => {
+                let time = self.departure + elapsed as f64;
+                let transit = self
+                    .canals
+                    .as_ref()
+                    .and_then(|(canals, _)| canals.transit_seconds(graph, edge_id));
+                let seconds = match transit {
+                    Some(seconds) => seconds,
+                    None => sailing_time.edge_seconds(graph, edge_id, time)?,
+                };
+                (seconds, time)
+            }
+            None => (edge.cost, self.departure),
+        };
+        let cost = match &self.canals {
+            Some((canals, closed)) => canals.weight(edge, cost, closed)?,
+            None => cost,
+        };
+        let cost = self.avoid_areas.weight(edge_id, cost)?;
+        let cost = match self.ice {
+            Some((zones, ice_class, penalty)) => {
+                zones.weight(edge_id, cost, month_of(time), ice_class, penalty)?
+            }
+            None => cost,
+        };
+        match self.emission_zones {
+            Some((zones, weight)) => {
+                Some(zones.weight(edge_id, cost, weight / self.meters_per_cost()))
+            }
+            None => Some(cost),
+        }
+    }
+
+    /// Returns the cost of the arc between a position and a node it is connected to, given its
+    /// meters. The fastest route sails them at the speed through the water. Like the edges, the
+    /// arc is blocked or penalized in the avoided areas and in ice, at the month of departure,
+    /// and blocked if the vessel fits none of the edges of the node.
+    fn connection_cost(
+        &self,
+        graph: &Graph,
+        position: &VirtualNode,
+        node_id: u32,
+        meters: u32,
+    ) -> Option<u32> {
+        let fits = |edge_id: u32| self.vessel.fits(&graph.clearances[edge_id as usize]);
+        if !(graph.outgoing_edge_ids(node_id).any(fits)
+            || graph
+                .incoming_edge_ids(node_id)
+                .iter()
+                .any(|&edge_id| fits(edge_id)))
+        {
+            return None;
+        }
+        let arc = position.arc(graph, node_id);
+        let cost = (meters as f64 / self.meters_per_cost()).round() as u32;
+        let cost = self.avoid_areas.arc_weight(&arc, cost)?;
+        match self.ice {
+            Some((zones, ice_class, penalty)) => {
+                let month = month_of(self.departure);
+                zones.arc_weight(&arc, cost, month, ice_class, penalty)
+            }
+            None => Some(cost),
+        }
+    }
+
+    /// Returns the meters sailed through the water per unit of cost.
+    fn meters_per_cost(&self) -> f64 {
+        match &self.fastest {
+            Some(sailing_time) => sailing_time.speed,
+            None => 1.0,
+        }
+    }
+}
+
+/// Routes from the position `from` along the via nodes to the position `to` with the workspace of
+/// the current worker thread. The first leg may start at any node connected to `from`, the last
+/// leg may end at any node connected to `to`.
+fn route_between(
+    graph: &Graph,
+    from: &VirtualNode,
+    via: &[u32],
+    to: &VirtualNode,
+    costs: &Costs,
+) -> Option<Vec<Route>> {
+    WORKSPACE.with(|workspace| {
+        let mut workspace = workspace.borrow_mut();
+        let workspace = workspace.get_or_insert_with(|| DijkstraWorkspace::new(graph.nodes.len()));
+        legs_between(graph, workspace, from, via, to, costs)
+    })
+}
+
+/// Routes leg by leg, each leg starts with the cost at the end of the leg before, so time
+/// dependent costs continue from there.
+fn legs_between(
+    graph: &Graph,
+    workspace: &mut DijkstraWorkspace,
+    from: &VirtualNode,
+    via: &[u32],
+    to: &VirtualNode,
+    costs: &Costs,
+) -> Option<Vec<Route>> {
+    let dijkstra = Dijkstra::new(graph);
+    let connections = |position: &VirtualNode| -> Vec<(u32, u32)> {
+        position
+            .connections
+            .iter()
+            .filter_map(|&(node_id, meters)| {
+                let cost = costs.connection_cost(graph, position, node_id, meters)?;
+                Some((node_id, cost))
+            })
+            .collect()
+    };
+    let mut legs: Vec<Route> = Vec::new();
+    for i in 0..=via.len() {
+        let sources = match legs.last() {
+            // the fastest route continues at the time the leg before arrived
+            Some(leg) if costs.fastest.is_some() => vec![(leg.end, workspace.cost(leg.end))],
+            Some(leg) => vec![(leg.end, 0)],
+            None => connections(from),
+        };
+        let targets = match via.get(i) {
+            Some(&via) => vec![(via, 0)],
+            None => connections(to),
+        };
+        let leg = dijkstra.route_between_time_dependent(
+            workspace,
+            &sources,
+            &targets,
+            |edge_id, edge, elapsed| costs.weight(graph, edge_id, edge, elapsed),
+        )?;
+        legs.push(leg);
+    }
+    Some(legs)
+}
+
+/// Stitches the legs into one LineString feature with `RouteProperties`. If the route runs
+/// between two positions, the first and last leg include the arcs to the positions. If a planet
+/// grid to smooth with is given, each leg is smoothed, the distances are the length of the
+/// geometry. If no route exists, the feature has no geometry and `found` is false. The stitched
+/// line is returned as well, e.g. to export it, with the range of its points of each leg.
+fn route_feature(
+    graph: &Graph,
+    legs: Option<&[Route]>,
+    endpoints: Option<(&VirtualNode, &VirtualNode)>,
+    smooth_with: Option<&PolygonSpatialPartition>,
+    emission_zones: Option<&EmissionZones>,
+    knots: Option<f64>,
+    took: Duration,
+) -> (Feature, Linestring, Vec<Range<usize>>) {
+    let duration = |meters: f64| knots.map(|knots| meters / METERS_PER_NAUTICAL_MILE / knots);
+    let found = legs.is_some();
+    let legs = legs.unwrap_or_default();
+    let mut points = Vec::new();
+    let mut leg_properties = Vec::new();
+    let mut leg_ranges = Vec::new();
+    for (i, leg) in legs.iter().enumerate() {
+        let mut leg_points = graph.convert_path(&leg.node_ids());
+        let mut leg_cost = leg.cost;
+        if let Some((from, to)) = endpoints {
+            if i == 0 {
+                leg_points.insert(0, from.point);
+                leg_cost += from.cost(leg.start).unwrap_or(0);
+            }
+            if i == legs.len() - 1 {
+                leg_points.push(to.point);
+                leg_cost += to.cost(leg.end).unwrap_or(0);
+            }
+        }
+        let mut leg_linestring = Linestring::new(leg_points);
+        if let Some(planet_grid) = smooth_with {
+            leg_linestring = leg_linestring.smooth(planet_grid);
+        }
+        let distance = leg_linestring.length();
+        leg_properties.push(LegProperties {
+            from: leg.start,
+            to: leg.end,
+            cost: leg_cost,
+            distance,
+            nautical_miles: distance / METERS_PER_NAUTICAL_MILE,
+            duration: duration(distance),
+            eca_distance: emission_zones.map(|zones| zones.meters_inside(&leg_linestring)),
+            geometry: leg_linestring.to_feature().geometry,
+        });
+
+        // the first point of a leg is the last point of the leg before
+        if !points.is_empty() {
+            points.pop();
+        }
+        let start = points.len();
+        points.extend(leg_linestring.points);
+        leg_ranges.push(start..points.len());
+    }
+
+    let snap = |position: &VirtualNode, node_id: Option<u32>| {
+        // without a route, the closest connected node is given
+        let node_id = node_id.unwrap_or(position.connections[0].0);
+        let node = &graph.nodes[node_id as usize];
+        Snap {
+            node: node_id,
+            position: (node.longitude(), node.latitude()),
+            distance: position.cost(node_id).unwrap_or(0),
+        }
+    };
+    let linestring = Linestring::new(points);
+    let distance = linestring.length();
+    let properties = RouteProperties {
+        found,
+        cost: found.then(|| leg_properties.iter().map(|leg| leg.cost).sum()),
+        distance,
+        nautical_miles: distance / METERS_PER_NAUTICAL_MILE,
+        duration: found.then(|| duration(distance)).flatten(),
+        eca_distance: emission_zones.map(|_| {
+            leg_properties
+                .iter()
+                .filter_map(|leg| leg.eca_distance)
+                .sum()
+        }),
+        from: endpoints.map(|(from, _)| snap(from, legs.first().map(|leg| leg.start))),
+        to: endpoints.map(|(_, to)| snap(to, legs.last().map(|leg| leg.end))),
+        legs: leg_properties,
+        took_ms: took.as_millis() as u64,
+    };
+
+    let mut feature = linestring.to_feature();
+    if !found {
+        feature.geometry = None;
+    }
+    feature.properties = serde_json::to_value(properties)
+        .ok()
+        .and_then(|properties| properties.as_object().cloned());
+    (feature, linestring, leg_ranges)
+}
+
+/// Answers that no route exists, for formats that cannot say so in the route itself.
+fn no_route_reply() -> warp::reply::Response {
+    error_reply(StatusCode::NOT_FOUND, "no route found")
+}
+
+/// Answers with the route as file in the format, with the waypoints thinned to `tolerance`
+/// meters, keeping clear of land if the planet grid is given.
+fn export(
+    linestring: &Linestring,
+    format: Format,
+    name: Option<&str>,
+    tolerance: Option<f64>,
+    planet_grid: Option<&PolygonSpatialPartition>,
+) -> Result<warp::reply::Response> {
+    let tolerance = tolerance.unwrap_or(DEFAULT_TOLERANCE);
+    if !(tolerance >= 0.0 && tolerance.is_finite()) {
+        return Err(Error::InvalidArgument(format!(
+            "the tolerance of {} meters is not a positive number",
+            tolerance
+        )));
+    }
+    let linestring = linestring.thin(tolerance, planet_grid);
+    let name = name.unwrap_or("Route");
+    let (body, content_type, extension) = match format {
+        Format::Geojson => unreachable!("GeoJSON is answered with the feature"),
+        Format::Gpx => (linestring.to_gpx(name), "application/gpx+xml", "gpx"),
+        Format::Kml => (
+            linestring.to_kml(name),
+            "application/vnd.google-earth.kml+xml",
+            "kml",
+        ),
+        Format::Rtz => (linestring.to_rtz(name), "application/xml", "rtz"),
+    };
+    Ok(Response::builder()
+        .header(CONTENT_TYPE, content_type)
+        .header(
+            CONTENT_DISPOSITION,
+            format!("attachment; filename=\"route.{}\"", extension),
+        )
+        .body(body.into())
+        .unwrap())
+}
+
+/// Returns an error if the speed is given, but not a positive number.
+fn check_speed(knots: Option<f64>) -> Result<()> {
+    match knots {
+        Some(knots) if !(knots > 0.0 && knots.is_finite()) => Err(Error::InvalidArgument(format!(
+            "the speed of {} knots is not a positive number",
+            knots
+        ))),
+        _ => Ok(()),
+    }
+}
+
+fn check_vessel(draft: f64, air_draft: f64) -> Result<Vessel> {
+    if !(draft >= 0.0 && draft.is_finite() && air_draft >= 0.0 && air_draft.is_finite()) {
+        return Err(Error::InvalidArgument(format!(
+            "the draft of {} m or the air draft of {} m is not a non-negative number",
+            draft, air_draft
+        )));
+    }
+    Ok(Vessel {
+        draft: draft as f32,
+        air_draft: air_draft as f32,
+    })
+}
+
+/// Finds the edges in the areas, given by their outlines, which are closed if needed.
+fn avoid_areas(
+    graph: &Graph,
+    outlines: &[Vec<(f64, f64)>],
+    penalty: Option<f64>,
+) -> Result<AvoidAreas> {
+    if let Some(penalty) = penalty.filter(|penalty| !(*penalty >= 1.0 && penalty.is_finite())) {
+        return Err(Error::InvalidArgument(format!(
+            "the penalty of {} is not a number of at least 1",
+            penalty
+        )));
+    }
+    let polygons = outlines
+        .iter()
+        .map(|outline| {
+            let mut outline = outline
+                .iter()
+                .map(|&(lon, lat)| Point::try_from_coordinate(lat, lon))
+                .collect::<Result<Vec<Point>>>()?;
+            if outline.len() < 3 {
+                return Err(Error::InvalidArgument(
+                    "an area needs an outline of at least 3 points".to_string(),
+                ));
+            }
+            if outline.first() != outline.last() {
+                outline.push(outline[0]);
+            }
+            Ok(Polygon::new(outline))
+        })
+        .collect::<Result<Vec<Polygon>>>()?;
+
+    let mut avoid_areas = AvoidAreas::new(graph, &polygons);
+    avoid_areas.penalty = penalty;
+    Ok(avoid_areas)
+}
+
+/// Answers with the reply, or with the error as JSON, with a 400 status if the request was
+/// invalid and a 500 status otherwise.
+fn reply(result: Result<impl Reply>) -> warp::reply::Response {
+    match result {
+        Ok(reply) => reply.into_response(),
+        Err(error) if error.is_invalid_input() => error_reply(StatusCode::BAD_REQUEST, error),
+        Err(error) => error_reply(StatusCode::INTERNAL_SERVER_ERROR, error),
+    }
+}
+
+fn error_reply(status: StatusCode, message: impl ToString) -> warp::reply::Response {
+    let body = warp::reply::json(&json!({ "error": message.to_string() }));
+    warp::reply::with_status(body, status).into_response()
+}
+
+/// Answers requests that no endpoint accepted with a JSON error, e.g. if the body is not valid.
+async fn handle_rejection(
+    rejection: Rejection,
+) -> std::result::Result<warp::reply::Response, Infallible> {
+    let response = if rejection.is_not_found() {
+        error_reply(StatusCode::NOT_FOUND, "not found")
+    } else if let Some(error) = rejection.find::<BodyDeserializeError>() {
+        error_reply(StatusCode::BAD_REQUEST, error)
+    } else if let Some(error) = rejection.find::<UnsupportedMediaType>() {
+        error_reply(StatusCode::UNSUPPORTED_MEDIA_TYPE, error)
+    } else if let Some(error) = rejection.find::<MethodNotAllowed>() {
+        error_reply(StatusCode::METHOD_NOT_ALLOWED, error)
+    } else {
+        error_reply(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("{:?}", rejection),
+        )
+    };
+    Ok(response)
+}
+
+/// Returns the ids of the nodes closest to the coordinates.
+fn nearest(graph: &Graph, coordinates: &[(f64, f64)]) -> Result<Vec<u32>> {
+    coordinates
+        .iter()
+        .map(|&(lon, lat)| Ok(graph.nearest(lon, lat)?.0))
+        .collect()
+}
+
+fn feature_collection(features: Vec<Feature>) -> String {
+    FeatureCollection {
+        bbox: None,
+        features,
+        foreign_members: None,
+    }
+    .to_string()
+}
+
+fn table(graph: &Graph, table_request: TableRequest) -> Result<impl Reply> {
+    let sources = nearest(graph, &table_request.sources)?;
+    let targets = nearest(graph, &table_request.targets)?;
+
+    let start = Instant::now();
+    let table = DistanceTable::new(graph, sources, targets);
+    println!(
+        "table_request: {:>4} x {:>4}, took: {:>5}ms",
+        table.sources.len(),
+        table.targets.len(),
+        start.elapsed().as_millis()
+    );
+
+    Ok(warp::reply::json(&TableResponse {
+        costs: table
+            .costs
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|&cost| (cost != u32::MAX).then_some(cost))
+                    .collect()
+            })
+            .collect(),
+        sources: table.sources,
+        targets: table.targets,
+    }))
+}
+
+fn voyage(
+    graph: &Graph,
+    planet_grid: Option<&PolygonSpatialPartition>,
+    emission_zones: Option<&EmissionZones>,
+    voyage_request: VoyageRequest,
+) -> Result<warp::reply::Response> {
+    if voyage_request.stops.len() < 2 {
+        return Err(Error::InvalidArgument(
+            "a voyage needs at least two stops".to_string(),
+        ));
+    }
+    let stops = nearest(graph, &voyage_request.stops)?;
+    check_speed(voyage_request.knots)?;
+    for index in [voyage_request.start, voyage_request.end]
+        .into_iter()
+        .flatten()
+    {
+        if index >= stops.len() {
+            return Err(Error::InvalidArgument(format!(
+                "there is no stop {}, only {} stops are given",
+                index,
+                stops.len()
+            )));
+        }
+    }
+
+    let start = Instant::now();
+    let table = DistanceTable::new(graph, stops.clone(), stops.clone());
+    let order = order_stops(&table.costs, voyage_request.start, voyage_request.end);
+    let waypoints: Vec<u32> = order.iter().map(|&i| stops[i]).collect();
+    let legs = route_via(graph, &waypoints);
+    let took = start.elapsed();
+    println!(
+        "voyage_request: {:>4} stops, took: {:>5}ms",
+        stops.len(),
+        took.as_millis()
+    );
+
+    let (mut feature, linestring, _) = route_feature(
+        graph,
+        legs.as_deref(),
+        None,
+        None,
+        emission_zones,
+        voyage_request.knots,
+        took,
+    );
+    match voyage_request.format {
+        Format::Geojson => {
+            if let Some(properties) = feature.properties.as_mut() {
+                properties.insert("order".to_string(), json!(order));
+            }
+            Ok(feature_collection(vec![feature]).into_response())
+        }
+        _ if legs.is_none() => Ok(no_route_reply()),
+        format => export(
+            &linestring,
+            format,
+            voyage_request.name.as_deref(),
+            voyage_request.tolerance,
+            planet_grid,
+        ),
+    }
+}
+
+fn alternatives(
+    graph: &Graph,
+    emission_zones: Option<&EmissionZones>,
+    alternatives_request: AlternativesRequest,
+) -> Result<impl Reply> {
+    let from = graph
+        .nearest(alternatives_request.from.0, alternatives_request.from.1)?
+        .0;
+    let to = graph
+        .nearest(alternatives_request.to.0, alternatives_request.to.1)?
+        .0;
+    let count = alternatives_request.count.unwrap_or(3);
+    check_speed(alternatives_request.knots)?;
+    if count > MAX_ALTERNATIVES {
+        return Err(Error::InvalidArgument(format!(
+            "at most {} alternatives can be requested",
+            MAX_ALTERNATIVES
+        )));
+    }
+
+    let start = Instant::now();
+    let routes = WORKSPACE.with(|workspace| {
+        let mut workspace = workspace.borrow_mut();
+        let workspace = workspace.get_or_insert_with(|| DijkstraWorkspace::new(graph.nodes.len()));
+        AlternativeRoutes::new(graph).routes(workspace, from, to, count)
+    });
+    let took = start.elapsed();
+    println!(
+        "alternatives_request: {:>7} -> {:>7}, {} routes, took: {:>5}ms",
+        from,
+        to,
+        routes.len(),
+        took.as_millis()
+    );
+
+    Ok(feature_collection(
+        routes
+            .iter()
+            .map(|route| {
+                let legs = Some(std::slice::from_ref(route));
+                let knots = alternatives_request.knots;
+                route_feature(graph, legs, None, None, emission_zones, knots, took).0
+            })
+            .collect(),
+    ))
+}
+
+fn isochrone(graph: &Graph, isochrone_request: IsochroneRequest) -> Result<impl Reply> {
+    let from = graph
+        .nearest(isochrone_request.from.0, isochrone_request.from.1)?
+        .0;
+    let nautical_miles = match (
+        isochrone_request.nautical_miles,
+        isochrone_request.hours,
+        isochrone_request.knots,
+    ) {
+        (Some(nautical_miles), _, _) => nautical_miles,
+        (None, Some(hours), Some(knots)) => hours * knots,
+        _ => {
+            return Err(Error::InvalidArgument(
+                "either nautical_miles or both hours and knots are required".to_string(),
+            ))
+        }
+    };
+    if !(nautical_miles >= 0.0 && nautical_miles.is_finite()) {
+        return Err(Error::InvalidArgument(format!(
+            "the distance of {} nautical miles is not a positive number",
+            nautical_miles
+        )));
+    }
+    let depth = isochrone_request.depth.unwrap_or(10);
+    if depth > MAX_ISOCHRONE_DEPTH {
+        return Err(Error::InvalidArgument(format!(
+            "the depth must be at most {}",
+            MAX_ISOCHRONE_DEPTH
+        )));
+    }
+    // edge costs are in meters
+    let max_cost = (nautical_miles * METERS_PER_NAUTICAL_MILE).min(u32::MAX as f64) as u32;
+
+    let start = Instant::now();
+    let isochrone = WORKSPACE.with(|workspace| {
+        let mut workspace = workspace.borrow_mut();
+        let workspace = workspace.get_or_insert_with(|| DijkstraWorkspace::new(graph.nodes.len()));
+        Isochrone::new(graph, workspace, from, max_cost, depth)
+    });
+    println!(
+        "isochrone_request: {:>7}, max cost: {:>9}, {:>7} nodes, took: {:>5}ms",
+        from,
+        max_cost,
+        isochrone.node_ids.len(),
+        start.elapsed().as_millis()
+    );
+
+    let mut feature = isochrone.area.to_feature();
+    let mut properties = JsonObject::new();
+    properties.insert("max_cost".to_string(), json!(isochrone.max_cost));
+    properties.insert("num_nodes".to_string(), json!(isochrone.node_ids.len()));
+    feature.properties = Some(properties);
+    Ok(feature_collection(vec![feature]))
+}
+
+fn route(
+    graph: &Graph,
+    planet_grid: Option<&PolygonSpatialPartition>,
+    emission_zones: Option<&EmissionZones>,
+    (currents, wind): (Option<&VectorField>, Option<&VectorField>),
+    ice_zones: Option<&IceZones>,
+    canals: Option<&Canals>,
+    route_request: RouteRequest,
+) -> Result<warp::reply::Response> {
+    let virtual_node = |(lon, lat): (f64, f64)| -> Result<VirtualNode> {
+        VirtualNode::new(
+            graph,
+            Point::try_from_coordinate(lat, lon)?,
+            planet_grid,
+            NUM_CONNECTIONS,
+        )
+    };
+    let from = virtual_node(route_request.from)?;
+    let to = virtual_node(route_request.to)?;
+    let via = nearest(graph, &route_request.via)?;
+    check_speed(route_request.knots)?;
+    let vessel = check_vessel(route_request.draft, route_request.air_draft)?;
+    let eca = match (route_request.eca_weight, emission_zones) {
+        (None, _) => None,
+        (Some(weight), _) if !(weight >= 0.0 && weight.is_finite()) => {
+            return Err(Error::InvalidArgument(format!(
+                "the ECA weight of {} is not a non-negative number",
+                weight
+            )))
+        }
+        (Some(weight), Some(zones)) => Some((zones, weight)),
+        (Some(_), None) => {
+            return Err(Error::InvalidArgument(
+                "no emission control areas are loaded".to_string(),
+            ))
+        }
+    };
+
+    let departure = match route_request.departure {
+        Some(departure) if !departure.is_finite() => {
+            return Err(Error::InvalidArgument(format!(
+                "the departure of {} is not a unix time",
+                departure
+            )))
+        }
+        Some(departure) => departure,
+        None => SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0.0, |now| now.as_secs_f64()),
+    };
+    let ice_class = route_request
+        .ice_class
+        .as_deref()
+        .map(str::parse::<IceClass>)
+        .transpose()?;
+    let ice = match (route_request.ice_penalty, ice_zones) {
+        (Some(penalty), _) if !(penalty >= 1.0 && penalty.is_finite()) => {
+            return Err(Error::InvalidArgument(format!(
+                "the ice penalty of {} is not a number of at least 1",
+                penalty
+            )))
+        }
+        (penalty, Some(zones)) => Some((zones, ice_class, penalty.unwrap_or(1.0))),
+        (None, None) => None,
+        (Some(_), None) => {
+            return Err(Error::InvalidArgument(
+                "no ice limits are loaded".to_string(),
+            ))
+        }
+    };
+    let closed_canals = match canals {
+        Some(canals) => route_request
+            .closed_canals
+            .iter()
+            .map(|name| {
+                canals.index(name).ok_or_else(|| {
+                    Error::InvalidArgument(format!("there is no canal named {}", name))
+                })
+            })
+            .collect::<Result<Vec<usize>>>()?,
+        None if route_request.closed_canals.is_empty() => Vec::new(),
+        None => return Err(Error::InvalidArgument("no canals are loaded".to_string())),
+    };
+    let sailing_time = route_request.knots.map(|knots| SailingTime {
+        currents,
+        wind,
+        ..SailingTime::new(knots)
+    });
+    let fastest = match sailing_time {
+        _ if !route_request.fastest => None,
+        Some(sailing_time) => Some(sailing_time),
+        None => {
+            return Err(Error::InvalidArgument(
+                "the fastest route needs a speed in knots".to_string(),
+            ))
+        }
+    };
+
+    let start = Instant::now();
+    let costs = Costs {
+        vessel,
+        avoid_areas: avoid_areas(graph, &route_request.avoid, route_request.avoid_penalty)?,
+        emission_zones: eca,
+        ice,
+        canals: canals.map(|canals| (canals, closed_canals)),
+        fastest,
+        departure,
+    };
+    let legs = route_between(graph, &from, &via, &to, &costs);
+    let time = start.elapsed();
+
+    let smooth_with = planet_grid.filter(|_| route_request.smooth);
+    let (mut feature, linestring, leg_ranges) = route_feature(
+        graph,
+        legs.as_deref(),
+        Some((&from, &to)),
+        smooth_with,
+        emission_zones,
+        route_request.knots,
+        time,
+    );
+    // each passage through a canal with the points of the leg it is in
+    let used_canals: Vec<(&Canal, Range<usize>)> = match (canals, legs.as_deref()) {
+        (Some(canals), Some(legs)) => legs
+            .iter()
+            .zip(leg_ranges)
+            .flat_map(|(leg, range)| {
+                canals
+                    .used_by(leg)
+                    .into_iter()
+                    .map(move |i| (&canals.canals()[i], range.clone()))
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    if let (Some(_), Some(properties)) = (canals, feature.properties.as_mut()) {
+        let canal_properties: Vec<_> = used_canals
+            .iter()
+            .map(|(canal, _)| {
+                json!({
+                    "name": canal.name,
+                    "transit_hours": canal.transit_hours,
+                    "toll": canal.toll,
+                })
+            })
+            .collect();
+        let tolls = used_canals
+            .iter()
+            .fold(0.0, |tolls, (canal, _)| tolls + canal.toll);
+        properties.insert("canals".to_string(), json!(canal_properties));
+        properties.insert("tolls".to_string(), json!(tolls));
+    }
+
+    // the times of arrival along the geometry with the currents and wind at those times and the
+    // transit times of the canals
+    let etas = sailing_time
+        .filter(|_| legs.is_some())
+        .and_then(|sailing_time| sailing_time.etas(&linestring.points, departure))
+        .map(|mut etas| {
+            for (canal, range) in used_canals {
+                canal.apply_transit(&linestring.points, &mut etas, range);
+            }
+            etas
+        });
+    if let (Some(etas), Some(properties)) = (etas, feature.properties.as_mut()) {
+        let arrival = etas.last().copied().unwrap_or(departure);
+        properties.insert(
+            "duration".to_string(),
+            json!((arrival - departure) / 3_600.0),
+        );
+        properties.insert("departure".to_string(), json!(departure));
+        properties.insert("arrival".to_string(), json!(arrival));
+        properties.insert("etas".to_string(), json!(etas));
+    }
+    let cost = feature
+        .property("cost")
+        .and_then(|cost| cost.as_u64())
+        .unwrap_or(u32::MAX as u64);
+
+    println!(
+        "route_request: {} -> {} via {:>2} waypoints, cost: {:>9}, took: {:>3}ms",
+        from.point,
+        to.point,
+        via.len(),
+        cost,
+        time.as_millis()
+    );
+    match route_request.format {
+        Format::Geojson => Ok(feature_collection(vec![feature]).into_response()),
+        _ if legs.is_none() => Ok(no_route_reply()),
+        format => export(
+            &linestring,
+            format,
+            route_request.name.as_deref(),
+            route_request.tolerance,
+            planet_grid,
+        ),
+    }
 }
 
 #[tokio::main]
-async fn main() {
+async fn main() -> Result<()> {
     let args = Args::parse();
+    let address: SocketAddr = args
+        .bind
+        .parse()
+        .map_err(|_| Error::InvalidArgument(format!("'{}' is not a socket address", args.bind)))?;
 
     let cors = warp::cors()
         .allow_any_origin() // For development. For production, specify allowed origins.
         .allow_headers(vec!["Content-Type"]) // Specify allowed headers
         .allow_methods(vec!["GET", "POST", "OPTIONS"]); // Specify allowed methods
 
+    let planet_grid = match args.planet_path {
+        Some(planet_path) => {
+            println!("Loading planet from file");
+            let planet = Planet::from_geojson_file(planet_path.as_str())?;
+            let mut planet_grid = PolygonSpatialPartition::new(50);
+            planet_grid.add_polygons(&planet.polygons);
+            Some(planet_grid)
+        }
+        None => None,
+    };
+    let planet_grid = Arc::new(planet_grid);
+
     println!("Loading graph from file");
     let time = Instant::now();
-    let graph = Graph::from_file(args.fmi_path.as_str());
+    let graph = if GraphFile::is_graph_file(args.fmi_path.as_str()) {
+        GraphFile::open(args.fmi_path.as_str())?.into_graph()
+    } else {
+        Graph::from_file(args.fmi_path.as_str())?
+    };
+    let (graph, canals) = match args.canals_path {
+        Some(canals_path) => {
+            let canals = Canal::from_geojson_file(canals_path.as_str())?;
+            let (graph, canals) = Canals::add_to(&graph, canals, planet_grid.as_ref().as_ref())?;
+            (graph, Some(canals))
+        }
+        None => (graph, None),
+    };
+    let canals = Arc::new(canals);
+    let graph = match args.avoid_path {
+        Some(avoid_path) => {
+            let polygons = Polygon::from_geojson_file(avoid_path.as_str())?;
+            println!("Removing the edges in {} avoided areas", polygons.len());
+            AvoidAreas::new(&graph, &polygons).remove_from(&graph)
+        }
+        None => graph,
+    };
     let graph = Arc::new(graph);
-    let fmi = Arc::new(Fmi::from_file(args.fmi_path.as_str()));
+    graph.point_grid();
     println!("Finished loading graph, took {:?}.", time.elapsed());
 
+    let emission_zones = match args.eca_path {
+        Some(eca_path) => {
+            let polygons = Polygon::from_geojson_file(eca_path.as_str())?;
+            println!(
+                "Measuring the edges in {} emission control areas",
+                polygons.len()
+            );
+            Some(EmissionZones::new(&graph, polygons))
+        }
+        None => None,
+    };
+    let emission_zones = Arc::new(emission_zones);
+
+    let vector_field = |path: Option<String>| -> Result<Arc<Option<VectorField>>> {
+        let field = match path {
+            Some(path) => {
+                println!("Loading {}", path);
+                Some(VectorField::from_file(path.as_str())?)
+            }
+            None => None,
+        };
+        Ok(Arc::new(field))
+    };
+    let currents = vector_field(args.currents_path)?;
+    let wind = vector_field(args.wind_path)?;
+
+    let ice_zones = match args.ice_path {
+        Some(ice_path) => {
+            let limits = IceLimit::from_geojson_file(ice_path.as_str())?;
+            println!("Finding the edges in {} ice limits", limits.len());
+            Some(IceZones::new(&graph, limits))
+        }
+        None => None,
+    };
+    let ice_zones = Arc::new(ice_zones);
+
     let frontend = warp::path::end().and(warp::fs::dir("public-html"));
-    let promote = warp::post()
-        .and(warp::path("route"))
+    let table = {
+        let graph = graph.clone();
+        warp::path("table")
+            .and(warp::post())
+            .and(warp::body::json())
+            .map(move |table_request| reply(table(&graph, table_request)))
+    };
+    let voyage = {
+        let graph = graph.clone();
+        let planet_grid = planet_grid.clone();
+        let emission_zones = emission_zones.clone();
+        warp::path("voyage")
+            .and(warp::post())
+            .and(warp::body::json())
+            .map(move |voyage_request| {
+                reply(voyage(
+                    &graph,
+                    planet_grid.as_ref().as_ref(),
+                    emission_zones.as_ref().as_ref(),
+                    voyage_request,
+                ))
+            })
+    };
+    let alternatives = {
+        let graph = graph.clone();
+        let emission_zones = emission_zones.clone();
+        warp::path("alternatives")
+            .and(warp::post())
+            .and(warp::body::json())
+            .map(move |alternatives_request| {
+                reply(alternatives(
+                    &graph,
+                    emission_zones.as_ref().as_ref(),
+                    alternatives_request,
+                ))
+            })
+    };
+    let isochrone = {
+        let graph = graph.clone();
+        warp::path("isochrone")
+            .and(warp::post())
+            .and(warp::body::json())
+            .map(move |isochrone_request| reply(isochrone(&graph, isochrone_request)))
+    };
+    let promote = warp::path("route")
+        .and(warp::post())
         .and(warp::body::json())
-        .map(move |route_request: RouteRequest| {
-            let from = fmi.nearest(route_request.from.0, route_request.from.1);
-            let to = fmi.nearest(route_request.to.0, route_request.to.1);
-
-            let dijkstra = Dijkstra::new(&graph);
-            let start = Instant::now();
-            let (used_edges, cost) = dijkstra.dijkstra(from, to);
-            let time = start.elapsed();
-            let route = get_route(&graph, from, to, used_edges);
-
-            let mut ids = Vec::new();
-            if let Some(route) = route {
-                ids.extend(route.edges.iter().map(|edge| edge.source_id));
-                if let Some(last) = route.edges.last() {
-                    ids.push(last.target_id);
-                }
-            }
-            let path = fmi.convert_path(&ids);
-            let linesstring = Linestring::new(path);
-            let mut planet = Planet::new();
-            planet.linestrings.push(linesstring);
+        .map(move |route_request| {
+            reply(route(
+                &graph,
+                planet_grid.as_ref().as_ref(),
+                emission_zones.as_ref().as_ref(),
+                (currents.as_ref().as_ref(), wind.as_ref().as_ref()),
+                ice_zones.as_ref().as_ref(),
+                canals.as_ref().as_ref(),
+                route_request,
+            ))
+        });
 
-            println!(
-                "route_request: {:>7} -> {:>7}, cost: {:>9}, took: {:>3}ms",
-                from,
-                to,
-                cost,
-                time.as_millis()
-            );
-            Response::builder().body(format!("{}", planet.to_geojson_str()))
-        })
+    let routes = frontend
+        .or(promote)
+        .or(table)
+        .or(voyage)
+        .or(alternatives)
+        .or(isochrone)
+        .recover(handle_rejection)
         .with(cors);
-
-    let routes = frontend.or(promote);
-    let address = args.bind.parse::<std::net::SocketAddr>().unwrap();
-    warp::serve(routes).run(address).await
+    warp::serve(routes).run(address).await;
+    Ok(())
 }
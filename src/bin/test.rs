@@ -1,8 +1,12 @@
 use std::time::{Duration, Instant};
 
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use indicatif::ProgressIterator;
-use osm_test::routing::{Dijkstra, Graph};
+use osm_test::routing::{
+    AStar, BidirectionalDijkstra, ContractionHierarchy, Dijkstra, DijkstraWorkspace, Graph,
+    GreatCircle, Landmarks,
+};
+use osm_test::{Error, Result};
 use rand::Rng;
 
 /// Starts a routing service on localhost:3030/route
@@ -12,14 +16,44 @@ struct Args {
     /// Path of .fmi file
     #[arg(short, long)]
     fmi_path: String,
+    /// Algorithm used for the queries
+    #[arg(short, long, value_enum, default_value_t = Algorithm::Dijkstra)]
+    algorithm: Algorithm,
+    /// Path of .landmarks file, needed for ALT
+    #[arg(short, long)]
+    landmarks_path: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Algorithm {
+    Dijkstra,
+    BidirectionalDijkstra,
+    AStar,
+    Alt,
+    ContractionHierarchy,
 }
 
-fn main() {
+fn main() -> Result<()> {
     let args = Args::parse();
 
-    let graph = Graph::from_file(args.fmi_path.as_str());
+    let graph = Graph::from_file(args.fmi_path.as_str())?;
     let number_nodes = graph.nodes.len();
     let dijkstra = Dijkstra::new(&graph);
+    let mut workspace = DijkstraWorkspace::new(number_nodes);
+    let bidirectional_dijkstra = BidirectionalDijkstra::new(&graph);
+    let a_star = AStar::new(&graph, GreatCircle::new(&graph));
+    let alt = match args.algorithm {
+        Algorithm::Alt => {
+            let path = args
+                .landmarks_path
+                .as_ref()
+                .ok_or_else(|| Error::InvalidArgument("ALT needs a landmarks file".to_string()))?;
+            Some(AStar::new(&graph, Landmarks::from_file(path, &graph)?))
+        }
+        _ => None,
+    };
+    let ch = matches!(args.algorithm, Algorithm::ContractionHierarchy)
+        .then(|| ContractionHierarchy::new(&graph));
 
     let mut rng = rand::thread_rng();
 
@@ -29,7 +63,13 @@ fn main() {
         let source = rng.gen_range(0..number_nodes) as u32;
         let target = rng.gen_range(0..number_nodes) as u32;
         let before = Instant::now();
-        let (_, cost) = dijkstra.dijkstra(source, target);
+        let cost = match args.algorithm {
+            Algorithm::Dijkstra => dijkstra.query(&mut workspace, source, target),
+            Algorithm::BidirectionalDijkstra => bidirectional_dijkstra.dijkstra(source, target).1,
+            Algorithm::AStar => a_star.a_star(source, target).1,
+            Algorithm::Alt => alt.as_ref().unwrap().a_star(source, target).1,
+            Algorithm::ContractionHierarchy => ch.as_ref().unwrap().query(source, target).1,
+        };
         times.push(before.elapsed());
         if cost == u32::MAX {
             println!("no route found");
@@ -41,4 +81,5 @@ fn main() {
         "average time was {:?}",
         times.iter().sum::<Duration>() / times.len() as u32
     );
+    Ok(())
 }
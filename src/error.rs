@@ -0,0 +1,72 @@
+use std::{fmt, io};
+
+/// The errors of the loaders and of invalid input, e.g. coordinates of a request.
+#[derive(Debug)]
+pub enum Error {
+    /// A file could not be opened or read.
+    Io { path: String, source: io::Error },
+    /// A file does not have the expected format, the message names the line or section.
+    Format { path: String, message: String },
+    /// An OSM PBF file could not be decoded.
+    Osm { path: String, source: osmpbf::Error },
+    /// A latitude outside of -90..=90 or a longitude outside of -180..=180.
+    InvalidCoordinate { latitude: f64, longitude: f64 },
+    /// A parameter that is out of its range, e.g. of a request.
+    InvalidArgument(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub(crate) fn io(path: &str, source: io::Error) -> Error {
+        Error::Io {
+            path: path.to_string(),
+            source,
+        }
+    }
+
+    pub(crate) fn format(path: &str, message: impl Into<String>) -> Error {
+        Error::Format {
+            path: path.to_string(),
+            message: message.into(),
+        }
+    }
+
+    /// Returns true if the error is caused by invalid input of the caller instead of a failure
+    /// while loading, e.g. to answer with a 4xx instead of a 5xx status.
+    pub fn is_invalid_input(&self) -> bool {
+        matches!(
+            self,
+            Error::InvalidCoordinate { .. } | Error::InvalidArgument(_)
+        )
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "could not read {}: {}", path, source),
+            Error::Format { path, message } => write!(f, "invalid file {}: {}", path, message),
+            Error::Osm { path, source } => write!(f, "could not decode {}: {}", path, source),
+            Error::InvalidCoordinate {
+                latitude,
+                longitude,
+            } => write!(
+                f,
+                "invalid coordinate: latitude {} or longitude {} is out of range",
+                latitude, longitude
+            ),
+            Error::InvalidArgument(message) => write!(f, "invalid argument: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            Error::Osm { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
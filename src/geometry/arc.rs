@@ -62,6 +62,17 @@ impl Arc {
         Point::from_n_vector(&((self.from.n_vector() + self.to.n_vector()) / 2.0))
     }
 
+    /// Returns points along the arc, excluding its ends, that are at most `max_step` radians apart.
+    pub fn sample(&self, max_step: f64) -> impl Iterator<Item = Point> {
+        let num_steps = (self.central_angle() / max_step).ceil().max(1.0) as usize;
+        let from = *self.from.n_vector();
+        let to = *self.to.n_vector();
+        (1..num_steps).map(move |step| {
+            let f = step as f64 / num_steps as f64;
+            Point::from_n_vector(&(from * (1.0 - f) + to * f))
+        })
+    }
+
     /// Returns the 'from' point of the arc, e.g. the starting point.
     pub fn from(&self) -> &Point {
         &self.from
@@ -138,6 +149,18 @@ impl Arc {
         a0 >= 0.0 && a1 <= 0.0
     }
 
+    /// Returns the central angle between the point and the closest point on the arc in radians.
+    pub fn distance_to(&self, point: &Point) -> f64 {
+        if self.between_normals(point) {
+            let sine = self.normal().dot(&point.n_vector().normalize()).abs();
+            sine.min(1.0).asin()
+        } else {
+            let to_from = Arc::new(point, &self.from).central_angle();
+            let to_to = Arc::new(point, &self.to).central_angle();
+            to_from.min(to_to)
+        }
+    }
+
     /// Returns the central angle of the arc in radians.
     pub fn central_angle(&self) -> f64 {
         let from = self.from.n_vector();
@@ -206,6 +229,29 @@ mod tests {
         assert!((angle - (PI / 2.0)).abs() < 1e-10, "angle was {}", angle);
     }
 
+    #[test]
+    fn test_distance_to() {
+        let from = Point::from_coordinate(0.0, 0.0);
+        let to = Point::from_coordinate(0.0, 10.0);
+        let arc = Arc::new(&from, &to);
+
+        // above the middle of the arc
+        let distance = arc.distance_to(&Point::from_coordinate(1.0, 5.0));
+        assert!(
+            (distance - 1f64.to_radians()).abs() < 1e-10,
+            "distance was {}",
+            distance
+        );
+
+        // beyond the end of the arc
+        let distance = arc.distance_to(&Point::from_coordinate(0.0, 12.0));
+        assert!(
+            (distance - 2f64.to_radians()).abs() < 1e-10,
+            "distance was {}",
+            distance
+        );
+    }
+
     #[test]
     fn test_intersection() {
         let outline_from = Point::from_coordinate(10.9602021, 119.7085977);
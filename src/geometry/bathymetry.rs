@@ -0,0 +1,94 @@
+use crate::{
+    geometry::{grid::Grid, Arc, Point},
+    text_reader::TextReader,
+    Result,
+};
+
+/// A grid of elevations in meters, e.g. GEBCO or ETOPO, negative below the sea level. Cells with
+/// an elevation above the sea level give no depth, so a coarse grid does not close narrow
+/// passages along the coast, the coastlines already keep the routes off land.
+pub struct Bathymetry {
+    grid: Grid,
+    /// Elevations row by row from north to south, NaN where unknown.
+    elevations: Vec<f32>,
+}
+
+impl Bathymetry {
+    /// Reads a grid in the ESRI ASCII format: a header of `ncols`, `nrows`, `xllcorner` or
+    /// `xllcenter`, `yllcorner` or `yllcenter`, `cellsize` and an optional `NODATA_value`, then
+    /// the elevations row by row from north to south.
+    pub fn from_file(path: &str) -> Result<Bathymetry> {
+        let mut reader = TextReader::open(path)?;
+        let (grid, line) = Grid::read_header(&mut reader, path)?;
+        println!("reading {}x{} elevations", grid.num_columns, grid.num_rows);
+        let elevations = grid.read_rows(&mut reader, path, line, 1)?;
+        Ok(Bathymetry { grid, elevations })
+    }
+
+    /// Returns the depth in meters at the point, `None` if the grid has no depth there.
+    pub fn depth_at(&self, point: &Point) -> Option<f32> {
+        let elevation = self.elevations[self.grid.index(point)?];
+        (elevation < 0.0).then_some(-elevation)
+    }
+
+    /// Returns the least depth along the arc in meters, sampled at every cell it crosses.
+    /// Infinite if the grid has no depth along the arc.
+    pub fn min_depth(&self, arc: &Arc) -> f32 {
+        let max_step = self.grid.cell_size.to_radians() / 2.0;
+        [*arc.from(), *arc.to()]
+            .into_iter()
+            .chain(arc.sample(max_step))
+            .filter_map(|point| self.depth_at(&point))
+            .fold(f32::INFINITY, f32::min)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        geometry::{Arc, Bathymetry, Point},
+        TestFile,
+    };
+
+    #[test]
+    fn depths_from_ascii_grid() {
+        // 4 by 3 cells of one degree, from 20 to 24 east and 10 to 13 north
+        let file = TestFile::new(
+            "bathymetry.asc",
+            "ncols 4\nnrows 3\nxllcorner 20\nyllcorner 10\ncellsize 1\nNODATA_value -9999\n\
+             -50 -40 -30 -20\n\
+             -50 -5 -9999 -20\n\
+             -50 -40 12 -20\n",
+        );
+        let bathymetry = Bathymetry::from_file(file.path()).unwrap();
+
+        let depth = |lat, lon| bathymetry.depth_at(&Point::from_coordinate(lat, lon));
+        assert_eq!(depth(12.5, 20.5), Some(50.0));
+        assert_eq!(depth(11.5, 21.5), Some(5.0));
+        assert_eq!(depth(11.5, 22.5), None);
+        assert_eq!(depth(10.5, 22.5), None);
+        assert_eq!(depth(9.5, 20.5), None);
+
+        let arc = |from: (f64, f64), to: (f64, f64)| {
+            Arc::new(
+                &Point::from_coordinate(from.0, from.1),
+                &Point::from_coordinate(to.0, to.1),
+            )
+        };
+        assert_eq!(bathymetry.min_depth(&arc((12.5, 20.5), (12.5, 23.5))), 20.0);
+        assert_eq!(bathymetry.min_depth(&arc((10.5, 21.5), (12.5, 21.5))), 5.0);
+        assert_eq!(
+            bathymetry.min_depth(&arc((5.0, 5.0), (6.0, 6.0))),
+            f32::INFINITY
+        );
+    }
+
+    #[test]
+    fn rows_with_missing_values_are_rejected() {
+        let file = TestFile::new(
+            "short_rows.asc",
+            "ncols 2\nnrows 2\nxllcenter 0\nyllcenter 0\ncellsize 1\n-1 -1\n-1\n",
+        );
+        assert!(Bathymetry::from_file(file.path()).is_err());
+    }
+}
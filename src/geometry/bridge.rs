@@ -0,0 +1,47 @@
+use geojson::Value;
+
+use crate::{
+    geometry::{geojson_points, read_geojson_features, Arc},
+    Error, Result,
+};
+
+/// A bridge or another obstacle above the water, e.g. a power line, with its vertical clearance
+/// above the water in meters.
+pub struct Bridge {
+    pub arcs: Vec<Arc>,
+    pub clearance: f32,
+}
+
+impl Bridge {
+    /// Reads the bridges of a GeoJSON file, the LineStrings of its features with a numeric
+    /// `clearance` property. Other geometries are skipped.
+    pub fn from_geojson_file(path: &str) -> Result<Vec<Bridge>> {
+        let mut bridges = Vec::new();
+        for (i, feature) in read_geojson_features(path)?.into_iter().enumerate() {
+            let lines = match feature.geometry.as_ref().map(|geometry| &geometry.value) {
+                Some(Value::LineString(line)) => vec![line.clone()],
+                Some(Value::MultiLineString(lines)) => lines.clone(),
+                _ => continue,
+            };
+            let clearance = feature
+                .property("clearance")
+                .and_then(|clearance| clearance.as_f64())
+                .ok_or_else(|| {
+                    Error::format(path, format!("feature {} has no numeric clearance", i))
+                })?;
+            for line in lines {
+                let points = geojson_points(path, &line)?;
+                let arcs = points
+                    .windows(2)
+                    .map(|points| Arc::new(&points[0], &points[1]))
+                    .collect();
+                bridges.push(Bridge {
+                    arcs,
+                    clearance: clearance as f32,
+                });
+            }
+        }
+
+        Ok(bridges)
+    }
+}
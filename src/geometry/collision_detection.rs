@@ -24,7 +24,7 @@ fn arc_point_collision(arc: &Arc, point: &Point) -> bool {
 }
 
 fn arc_polygon_collision(arc: &Arc, polygon: &Polygon) -> bool {
-    polygon.intersections(arc).is_empty()
+    !polygon.intersections(arc).is_empty()
         || polygon.contains(arc.from())
         || polygon.contains(arc.to())
 }
@@ -169,3 +169,30 @@ impl Contains<Arc> for ConvecQuadrilateral {
         self.contains(rhs.from()) && self.contains(rhs.to())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::{Arc, Collides, Point, Polygon};
+
+    #[test]
+    fn arcs_collide_with_polygons_they_cross_or_touch() {
+        let point = |lat, lon| Point::from_coordinate(lat, lon);
+        let polygon = Polygon::new(vec![
+            point(10.0, 20.0),
+            point(10.0, 21.0),
+            point(11.0, 21.0),
+            point(11.0, 20.0),
+            point(10.0, 20.0),
+        ]);
+
+        // across the polygon, both ends outside
+        assert!(Arc::new(&point(10.5, 19.5), &point(10.5, 21.5)).collides(&polygon));
+        // from inside to outside
+        assert!(Arc::new(&point(10.5, 20.5), &point(10.5, 21.5)).collides(&polygon));
+        // inside
+        assert!(Arc::new(&point(10.4, 20.4), &point(10.6, 20.6)).collides(&polygon));
+        // next to the polygon
+        assert!(!Arc::new(&point(12.0, 19.5), &point(12.0, 21.5)).collides(&polygon));
+        assert!(!polygon.collides(&Arc::new(&point(9.0, 20.5), &point(9.5, 20.5))));
+    }
+}
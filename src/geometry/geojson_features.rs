@@ -0,0 +1,44 @@
+use std::str::FromStr;
+
+use geojson::{Feature, GeoJson, Value};
+
+use crate::{geometry::Point, Error, Result};
+
+/// Reads the features of a GeoJSON file, which holds a FeatureCollection or a single Feature. A
+/// file with a bare Geometry has no features.
+pub fn read_geojson_features(path: &str) -> Result<Vec<Feature>> {
+    let content = std::fs::read_to_string(path).map_err(|error| Error::io(path, error))?;
+    let geojson =
+        GeoJson::from_str(&content).map_err(|error| Error::format(path, error.to_string()))?;
+    Ok(match geojson {
+        GeoJson::FeatureCollection(collection) => collection.features,
+        GeoJson::Feature(feature) => vec![feature],
+        GeoJson::Geometry(_) => Vec::new(),
+    })
+}
+
+/// Converts the positions of a feature of the file at `path` to points, or returns a format
+/// error if a position is invalid.
+pub fn geojson_points(path: &str, positions: &[Vec<f64>]) -> Result<Vec<Point>> {
+    positions
+        .iter()
+        .map(|position| Point::try_from_geojson_vec(position))
+        .collect::<Result<Vec<Point>>>()
+        .map_err(|error| Error::format(path, error.to_string()))
+}
+
+/// Returns the outlines of a Polygon or the Polygons of a MultiPolygon of the file at `path`, the
+/// first ring of each, holes are ignored. Other geometries have no outlines.
+pub fn geojson_outlines(path: &str, value: &Value) -> Result<Vec<Vec<Point>>> {
+    let outline = |rings: &Vec<Vec<Vec<f64>>>| {
+        let outline = rings
+            .first()
+            .ok_or_else(|| Error::format(path, "a polygon has no rings"))?;
+        geojson_points(path, outline)
+    };
+    match value {
+        Value::Polygon(rings) => Ok(vec![outline(rings)?]),
+        Value::MultiPolygon(polygons) => polygons.iter().map(outline).collect(),
+        _ => Ok(Vec::new()),
+    }
+}
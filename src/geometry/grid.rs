@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+
+use crate::{geometry::Point, text_reader::TextReader, Error, Result};
+
+/// The cells of a regular grid of longitudes and latitudes, as given by the header of an ESRI
+/// ASCII grid: `ncols`, `nrows`, `xllcorner` or `xllcenter`, `yllcorner` or `yllcenter`,
+/// `cellsize` and an optional `NODATA_value`. The rows run from north to south.
+pub(crate) struct Grid {
+    pub num_columns: usize,
+    pub num_rows: usize,
+    /// Longitude and latitude of the center of the south west cell in degrees.
+    longitude: f64,
+    latitude: f64,
+    pub cell_size: f64,
+    no_data: Option<f32>,
+}
+
+impl Grid {
+    /// Reads the header, returns the grid and the first line after the header.
+    pub fn read_header(reader: &mut TextReader, path: &str) -> Result<(Grid, String)> {
+        let mut header = HashMap::new();
+        let mut line = reader.next_line()?;
+        while line
+            .trim_start()
+            .starts_with(|c: char| c.is_ascii_alphabetic())
+        {
+            let mut values = line.split_whitespace();
+            let key = values.next().unwrap().to_lowercase();
+            // keys of the body, e.g. the time of a step, end the header
+            if !["ncols", "nrows", "cellsize", "nodata_value"].contains(&key.as_str())
+                && !key.starts_with("xll")
+                && !key.starts_with("yll")
+            {
+                break;
+            }
+            header.insert(key, reader.parse::<f64>(values.next())?);
+            line = reader.next_line()?;
+        }
+        let value = |key: &str| {
+            header
+                .get(key)
+                .copied()
+                .ok_or_else(|| Error::format(path, format!("missing {} in header", key)))
+        };
+        let cell_size = value("cellsize")?;
+        let center = |key: &str| {
+            value(&format!("{}center", key)).or_else(|_| {
+                value(&format!("{}corner", key)).map(|corner| corner + cell_size / 2.0)
+            })
+        };
+
+        let grid = Grid {
+            num_columns: value("ncols")? as usize,
+            num_rows: value("nrows")? as usize,
+            longitude: center("xll")?,
+            latitude: center("yll")?,
+            cell_size,
+            no_data: header.get("nodata_value").map(|&no_data| no_data as f32),
+        };
+        Ok((grid, line))
+    }
+
+    /// Reads `num_rows` rows of `values_per_cell` values per cell, starting with `line`. Values
+    /// equal to `NODATA_value` are NaN.
+    pub fn read_rows(
+        &self,
+        reader: &mut TextReader,
+        path: &str,
+        mut line: String,
+        values_per_cell: usize,
+    ) -> Result<Vec<f32>> {
+        let values_per_row = self.num_columns * values_per_cell;
+        let mut values = Vec::with_capacity(self.num_rows * values_per_row);
+        for row in 0..self.num_rows {
+            if row > 0 {
+                line = reader.next_line()?;
+            }
+            for value in line.split_whitespace() {
+                let value: f32 = reader.parse(Some(value))?;
+                values.push(match self.no_data {
+                    Some(no_data) if value == no_data => f32::NAN,
+                    _ => value,
+                });
+            }
+            if values.len() != (row + 1) * values_per_row {
+                return Err(Error::format(
+                    path,
+                    format!("expected {} values in row {}", values_per_row, row + 1),
+                ));
+            }
+        }
+        Ok(values)
+    }
+
+    /// Returns the index of the cell of the point, row by row from north to south, `None` if the
+    /// point is outside of the grid.
+    pub fn index(&self, point: &Point) -> Option<usize> {
+        let mut column = ((point.longitude() - self.longitude) / self.cell_size).round();
+        // a grid around the whole planet continues across the antimeridian
+        if self.num_columns as f64 * self.cell_size >= 360.0 {
+            column = column.rem_euclid(self.num_columns as f64);
+        }
+        let row = self.num_rows as f64
+            - 1.0
+            - ((point.latitude() - self.latitude) / self.cell_size).round();
+        if !(0.0..self.num_columns as f64).contains(&column)
+            || !(0.0..self.num_rows as f64).contains(&row)
+        {
+            return None;
+        }
+        Some(row as usize * self.num_columns + column as usize)
+    }
+}
@@ -1,6 +1,9 @@
 use geojson::{Feature, Geometry, Value};
 
-use crate::{geometry::Arc, geometry::Point};
+use crate::{
+    geometry::{radians_to_meter, Arc, Point},
+    spatial_partition::PolygonSpatialPartition,
+};
 
 #[derive(Clone)]
 pub struct Linestring {
@@ -15,13 +18,185 @@ impl Linestring {
             // .flatten()
             .collect();
         let mut points: Vec<Point> = arcs.iter().map(|arc| arc.from().clone()).collect();
-        if !points.is_empty() {
-            points.push(points.last().unwrap().clone());
+        if let Some(last) = arcs.last() {
+            points.push(*last.to());
+        }
+
+        Linestring { points }
+    }
+
+    /// Returns the length of the linestring in meters.
+    pub fn length(&self) -> f64 {
+        self.points
+            .windows(2)
+            .map(|arc| radians_to_meter(Arc::new(&arc[0], &arc[1]).central_angle()))
+            // an empty sum of floats is -0.0
+            .fold(0.0, |length, arc_length| length + arc_length)
+    }
+
+    /// Removes intermediate points where the direct arc between the points around them does not
+    /// collide with land, e.g. to straighten the zig-zag of routes on the node grid. Starting at
+    /// the first point, the line is followed as long as the direct arc from the current point
+    /// stays free, the last reachable point becomes the next current point. The arcs of the
+    /// original linestring are assumed to be free, every new arc is checked against
+    /// `planet_grid`.
+    pub fn smooth(&self, planet_grid: &PolygonSpatialPartition) -> Linestring {
+        let Some(&first) = self.points.first() else {
+            return self.clone();
+        };
+
+        let mut points = vec![first];
+        let mut current = 0;
+        while current + 1 < self.points.len() {
+            let mut next = current + 1;
+            while next + 1 < self.points.len()
+                && !planet_grid
+                    .check_collision(&Arc::new(&self.points[current], &self.points[next + 1]))
+            {
+                next += 1;
+            }
+            points.push(self.points[next]);
+            current = next;
         }
 
         Linestring { points }
     }
 
+    /// Removes intermediate points that are at most `tolerance` meters off the direct arc between
+    /// the points kept around them (Douglas-Peucker), e.g. to reduce a route to few waypoints for
+    /// a chart plotter. If a planet grid is given, a direct arc that collides with land is split
+    /// as if a point were too far off, so the thinned line stays clear of land like the original.
+    pub fn thin(
+        &self,
+        tolerance: f64,
+        planet_grid: Option<&PolygonSpatialPartition>,
+    ) -> Linestring {
+        if self.points.len() <= 2 {
+            return self.clone();
+        }
+
+        let mut keep = vec![false; self.points.len()];
+        keep[0] = true;
+        keep[self.points.len() - 1] = true;
+        let mut sections = vec![(0, self.points.len() - 1)];
+        while let Some((start, end)) = sections.pop() {
+            if end - start < 2 {
+                continue;
+            }
+            let arc = Arc::new(&self.points[start], &self.points[end]);
+            let (farthest, distance) = (start + 1..end)
+                .map(|i| (i, radians_to_meter(arc.distance_to(&self.points[i]))))
+                .max_by(|a, b| a.1.total_cmp(&b.1))
+                .unwrap();
+            let collides = planet_grid.is_some_and(|planet_grid| planet_grid.check_collision(&arc));
+            if distance > tolerance || collides {
+                keep[farthest] = true;
+                sections.push((start, farthest));
+                sections.push((farthest, end));
+            }
+        }
+
+        let points = self
+            .points
+            .iter()
+            .zip(keep)
+            .filter(|&(_, keep)| keep)
+            .map(|(point, _)| *point)
+            .collect();
+        Linestring { points }
+    }
+
+    /// Returns the linestring as GPX route, with numbered waypoints.
+    pub fn to_gpx(&self, name: &str) -> String {
+        let mut gpx = String::new();
+        gpx.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        gpx.push_str(
+            "<gpx version=\"1.1\" creator=\"ship_routing\" \
+             xmlns=\"http://www.topografix.com/GPX/1/1\">\n",
+        );
+        gpx.push_str("  <rte>\n");
+        gpx.push_str(&format!("    <name>{}</name>\n", escape_xml(name)));
+        for (i, point) in self.points.iter().enumerate() {
+            gpx.push_str(&format!(
+                "    <rtept lat=\"{:.6}\" lon=\"{:.6}\"><name>{}</name></rtept>\n",
+                point.latitude(),
+                point.longitude(),
+                waypoint_name(i)
+            ));
+        }
+        gpx.push_str("  </rte>\n");
+        gpx.push_str("</gpx>\n");
+        gpx
+    }
+
+    /// Returns the linestring as KML document, with the line and a placemark for every waypoint.
+    pub fn to_kml(&self, name: &str) -> String {
+        let mut kml = String::new();
+        kml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        kml.push_str("<kml xmlns=\"http://www.opengis.net/kml/2.2\">\n");
+        kml.push_str("  <Document>\n");
+        kml.push_str(&format!("    <name>{}</name>\n", escape_xml(name)));
+        kml.push_str("    <Placemark>\n");
+        kml.push_str(&format!("      <name>{}</name>\n", escape_xml(name)));
+        kml.push_str("      <LineString>\n");
+        kml.push_str("        <tessellate>1</tessellate>\n");
+        let coordinates: Vec<String> = self
+            .points
+            .iter()
+            .map(|point| format!("{:.6},{:.6}", point.longitude(), point.latitude()))
+            .collect();
+        kml.push_str(&format!(
+            "        <coordinates>{}</coordinates>\n",
+            coordinates.join(" ")
+        ));
+        kml.push_str("      </LineString>\n");
+        kml.push_str("    </Placemark>\n");
+        for (i, coordinate) in coordinates.iter().enumerate() {
+            kml.push_str(&format!(
+                "    <Placemark><name>{}</name>\
+                 <Point><coordinates>{}</coordinates></Point></Placemark>\n",
+                waypoint_name(i),
+                coordinate
+            ));
+        }
+        kml.push_str("  </Document>\n");
+        kml.push_str("</kml>\n");
+        kml
+    }
+
+    /// Returns the linestring as RTZ route (IEC 61174), the exchange format of ECDIS. The legs
+    /// are great circles, like the arcs of the linestring.
+    pub fn to_rtz(&self, name: &str) -> String {
+        let mut rtz = String::new();
+        rtz.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        rtz.push_str("<route version=\"1.0\" xmlns=\"http://www.cirm.org/RTZ/1/0\">\n");
+        rtz.push_str(&format!(
+            "  <routeInfo routeName=\"{}\"/>\n",
+            escape_xml(name)
+        ));
+        rtz.push_str("  <waypoints>\n");
+        for (i, point) in self.points.iter().enumerate() {
+            rtz.push_str(&format!(
+                "    <waypoint id=\"{}\" name=\"{}\">\n",
+                i + 1,
+                waypoint_name(i)
+            ));
+            rtz.push_str(&format!(
+                "      <position lat=\"{:.6}\" lon=\"{:.6}\"/>\n",
+                point.latitude(),
+                point.longitude()
+            ));
+            // the leg leads to this waypoint, so the first waypoint has none
+            if i > 0 {
+                rtz.push_str("      <leg geometryType=\"Orthodrome\"/>\n");
+            }
+            rtz.push_str("    </waypoint>\n");
+        }
+        rtz.push_str("  </waypoints>\n");
+        rtz.push_str("</route>\n");
+        rtz
+    }
+
     pub fn to_feature(&self) -> Feature {
         let mut points: Vec<_> = self.points.iter().map(|p| p.to_geojson_vec()).collect();
 
@@ -62,3 +237,104 @@ impl Linestring {
         }
     }
 }
+
+fn waypoint_name(index: usize) -> String {
+    format!("WP{:03}", index + 1)
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        geometry::{radians_to_meter, Arc, Linestring, Point, Polygon},
+        spatial_partition::PolygonSpatialPartition,
+    };
+
+    #[test]
+    fn smoothing_keeps_clear_of_land() {
+        let island = Polygon::new(vec![
+            Point::from_coordinate(10.2, 20.2),
+            Point::from_coordinate(10.2, 20.4),
+            Point::from_coordinate(10.4, 20.4),
+            Point::from_coordinate(10.4, 20.2),
+            Point::from_coordinate(10.2, 20.2),
+        ]);
+        let mut planet_grid = PolygonSpatialPartition::new(50);
+        planet_grid.add_polygons(&vec![island]);
+
+        // a zig-zag line along the south of the island and up along its east side
+        let mut points: Vec<Point> = (0..=10)
+            .map(|i| Point::from_coordinate(10.1 + (i % 2) as f64 * 0.05, 20.0 + i as f64 * 0.05))
+            .collect();
+        points.extend(
+            (1..=10).map(|i| {
+                Point::from_coordinate(10.1 + i as f64 * 0.05, 20.5 + (i % 2) as f64 * 0.05)
+            }),
+        );
+        let linestring = Linestring::new(points);
+        assert!(linestring
+            .points
+            .windows(2)
+            .all(|arc| !planet_grid.check_collision(&Arc::new(&arc[0], &arc[1]))));
+
+        let smoothed = linestring.smooth(&planet_grid);
+        assert!(smoothed.points.len() < linestring.points.len());
+        assert!(smoothed.length() < linestring.length());
+        assert!(smoothed.points.first() == linestring.points.first());
+        assert!(smoothed.points.last() == linestring.points.last());
+        assert!(smoothed
+            .points
+            .windows(2)
+            .all(|arc| !planet_grid.check_collision(&Arc::new(&arc[0], &arc[1]))));
+    }
+
+    #[test]
+    fn thinning_keeps_corners_and_stays_within_tolerance() {
+        // two straight sections along the equator and a meridian, with many points each
+        let mut points: Vec<Point> = (0..=20)
+            .map(|i| Point::from_coordinate(0.0, i as f64 * 0.05))
+            .collect();
+        points.extend((1..=20).map(|i| Point::from_coordinate(i as f64 * 0.05, 1.0)));
+        let linestring = Linestring::new(points);
+
+        let thinned = linestring.thin(100.0, None);
+        assert_eq!(thinned.points.len(), 3);
+        assert!(thinned.points[1].is_approximately_equal(&Point::from_coordinate(0.0, 1.0)));
+        assert!(linestring.points.iter().all(|point| {
+            thinned
+                .points
+                .windows(2)
+                .any(|arc| radians_to_meter(Arc::new(&arc[0], &arc[1]).distance_to(point)) <= 100.0)
+        }));
+    }
+
+    #[test]
+    fn exports_name_every_waypoint() {
+        let linestring = Linestring::new(vec![
+            Point::from_coordinate(54.0, 10.0),
+            Point::from_coordinate(54.5, 11.0),
+            Point::from_coordinate(55.0, 11.5),
+        ]);
+
+        let gpx = linestring.to_gpx("Kiel & back");
+        assert_eq!(gpx.matches("<rtept ").count(), 3);
+        assert!(gpx.contains("<name>Kiel &amp; back</name>"));
+        assert!(gpx.contains("<rtept lat=\"54.500000\" lon=\"11.000000\"><name>WP002</name>"));
+
+        let kml = linestring.to_kml("Kiel & back");
+        assert!(kml.contains("<coordinates>10.000000,54.000000 11.000000,54.500000"));
+        assert_eq!(kml.matches("<Point>").count(), 3);
+
+        let rtz = linestring.to_rtz("Kiel & back");
+        assert!(rtz.contains("routeName=\"Kiel &amp; back\""));
+        assert_eq!(rtz.matches("<waypoint ").count(), 3);
+        assert_eq!(rtz.matches("<leg ").count(), 2);
+    }
+}
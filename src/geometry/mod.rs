@@ -1,15 +1,24 @@
 mod arc;
+mod bathymetry;
+mod bridge;
 mod collision_detection;
+mod geojson_features;
+mod grid;
 mod linestring;
 mod osm_data;
 mod planet;
 mod point;
 mod polygon;
+mod vector_field;
 
 pub use arc::Arc;
+pub use bathymetry::Bathymetry;
+pub use bridge::Bridge;
 pub use collision_detection::*;
+pub use geojson_features::*;
 pub use linestring::Linestring;
 pub use osm_data::*;
 pub use planet::Planet;
 pub use point::*;
 pub use polygon::Polygon;
+pub use vector_field::VectorField;
@@ -3,6 +3,8 @@ use std::collections::HashMap;
 use indicatif::ProgressBar;
 use osmpbf::{Element, ElementReader};
 
+use crate::{Error, Result};
+
 use super::{Planet, Point, Polygon};
 
 /// a planet struct which ways are not cloesed
@@ -29,18 +31,22 @@ impl OsmData {
         planet
     }
 
-    pub fn from_path(path: &str) -> OsmData {
+    pub fn from_path(path: &str) -> Result<OsmData> {
         let mut nodes = HashMap::new();
         let mut coastlines = Vec::new();
+        let osm_error = |source| Error::Osm {
+            path: path.to_string(),
+            source,
+        };
 
-        let reader = ElementReader::from_path(path).unwrap();
+        let reader = ElementReader::from_path(path).map_err(osm_error)?;
         let elements = reader
             .par_map_reduce(|_| 1, || 0_u64, |a, b| a + b)
-            .unwrap();
+            .map_err(osm_error)?;
         println!("there are {}", elements);
 
         let pb = ProgressBar::new(elements);
-        let reader = ElementReader::from_path(path).unwrap();
+        let reader = ElementReader::from_path(path).map_err(osm_error)?;
         reader
             .for_each(|element| {
                 pb.inc(1);
@@ -60,13 +66,13 @@ impl OsmData {
                     _ => (),
                 }
             })
-            .unwrap();
+            .map_err(osm_error)?;
         pb.finish();
         println!("finished reading");
 
         let mut raw_osm_data = OsmData { nodes, coastlines };
         raw_osm_data.close_coastline();
-        raw_osm_data
+        Ok(raw_osm_data)
     }
 
     fn close_coastline(&mut self) {
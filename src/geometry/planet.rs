@@ -1,5 +1,4 @@
 use std::{
-    error::Error,
     fs::File,
     io::{BufRead, BufReader, BufWriter, Write},
     str::FromStr,
@@ -11,8 +10,11 @@ use imageproc::{drawing::draw_antialiased_line_segment_mut, pixelops::interpolat
 use indicatif::ProgressIterator;
 
 use crate::{geometry::Arc, geometry::Linestring, geometry::Point, geometry::Polygon};
+use crate::{Error, Result};
 
-use super::{collision_detection::CollisionDetection, Contains, OsmData};
+use super::{
+    collision_detection::CollisionDetection, geojson_outlines, geojson_points, Contains, OsmData,
+};
 
 #[derive(Clone)]
 pub struct Planet {
@@ -52,9 +54,9 @@ impl Planet {
             .collect()
     }
 
-    pub fn from_osm_file(path: &str) -> Self {
-        let raw_osm_data = OsmData::from_path(path);
-        raw_osm_data.to_planet()
+    pub fn from_osm_file(path: &str) -> Result<Self> {
+        let raw_osm_data = OsmData::from_path(path)?;
+        Ok(raw_osm_data.to_planet())
     }
 
     pub fn to_image(&self, path: &str) {
@@ -95,27 +97,41 @@ impl Planet {
         image.save(path).unwrap();
     }
 
-    pub fn from_geojson_file(path: &str) -> Result<Planet, Box<dyn Error>> {
-        let reader = BufReader::new(File::open(path).unwrap());
+    pub fn from_geojson_file(path: &str) -> Result<Planet> {
+        let file = File::open(path).map_err(|error| Error::io(path, error))?;
         let mut planet = Planet::new();
 
-        reader
-            .lines()
-            .filter_map(|line| {
-                let mut line = line.unwrap();
-                if line.chars().last() == Some(',') {
-                    line.pop();
+        for line in BufReader::new(file).lines() {
+            let mut line = line.map_err(|error| Error::io(path, error))?;
+            if line.ends_with(',') {
+                line.pop();
+            }
+            // lines without a feature, e.g. the brackets of the collection, are skipped
+            let Some(geometry) = Feature::from_str(line.as_str())
+                .ok()
+                .and_then(|feature| feature.geometry)
+            else {
+                continue;
+            };
+            match &geometry.value {
+                Value::Point(position) => planet
+                    .points
+                    .extend(geojson_points(path, std::slice::from_ref(position))?),
+                Value::Polygon(_) => {
+                    let outlines = geojson_outlines(path, &geometry.value)?;
+                    planet
+                        .polygons
+                        .extend(outlines.into_iter().map(Polygon::new));
+                }
+                Value::LineString(line) => {
+                    let [from, to, ..] = geojson_points(path, line)?[..] else {
+                        return Err(Error::format(path, "a line has less than two positions"));
+                    };
+                    planet.arcs.push(Arc::new(&from, &to));
                 }
-                Feature::from_str(line.as_str()).ok()?.geometry
-            })
-            .for_each(|geometry| match geometry.value {
-                Value::Point(point) => planet.points.push(Point::from_geojson_vec(point)),
-                Value::Polygon(polygon) => planet
-                    .polygons
-                    .push(Polygon::from_geojson_vec(polygon[0].clone())),
-                Value::LineString(line) => planet.arcs.push(Arc::from_geojson_vec(line)),
                 _ => (),
-            });
+            }
+        }
 
         Ok(planet)
     }
@@ -180,3 +196,37 @@ pub fn scale(input: f64, input_min: f64, input_max: f64, output_min: u32, output
     let scaled_value = ((input - input_min) / input_range) * output_range;
     (scaled_value + output_min as f64).round() as i32
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{geometry::Planet, TestFile};
+
+    #[test]
+    fn invalid_geojson_is_a_format_error() {
+        let read = |feature: &str| {
+            let file = TestFile::new(
+                "planet.geojson",
+                format!(
+                    "{{\"type\":\"FeatureCollection\",\"features\":[\n{}\n]}}\n",
+                    feature
+                ),
+            );
+            Planet::from_geojson_file(file.path())
+        };
+        let feature = |geometry: &str| {
+            format!(
+                "{{\"type\":\"Feature\",\"properties\":{{}},\"geometry\":{}}}",
+                geometry
+            )
+        };
+
+        let planet = read(&feature(r#"{"type":"Point","coordinates":[20.0,10.0]}"#)).unwrap();
+        assert_eq!(planet.points.len(), 1);
+        assert!(read(&feature(r#"{"type":"Point","coordinates":[20.0,100.0]}"#)).is_err());
+        assert!(read(&feature(r#"{"type":"Polygon","coordinates":[]}"#)).is_err());
+        assert!(read(&feature(
+            r#"{"type":"LineString","coordinates":[[20.0,10.0]]}"#
+        ))
+        .is_err());
+    }
+}
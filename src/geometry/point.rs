@@ -1,4 +1,5 @@
 use crate::geometry::Arc;
+use crate::{Error, Result};
 use std::{f64::consts::PI, fmt, hash::Hash};
 
 use geojson::{Feature, Geometry, Value};
@@ -45,6 +46,19 @@ impl PointGenerator {
 }
 
 impl Point {
+    /// Creates a `Point` like `from_coordinate`, but returns an error instead of panicking if the
+    /// values are out of range, e.g. for coordinates of a request.
+    pub fn try_from_coordinate(latitude: f64, longitude: f64) -> Result<Point> {
+        if (-90.0..=90.0).contains(&latitude) && (-180.0..=180.0).contains(&longitude) {
+            Ok(Point::from_coordinate(latitude, longitude))
+        } else {
+            Err(Error::InvalidCoordinate {
+                latitude,
+                longitude,
+            })
+        }
+    }
+
     /// Creates a `Point` from given latitude and longitude values, asserting that they are within valid ranges
     /// (-90.0 <= latitude <= 90, -180.0 <= longitude <= 180.0).
     pub fn from_coordinate(latitude: f64, longitude: f64) -> Point {
@@ -166,6 +180,18 @@ impl Point {
         Point::from_coordinate(vec[1], vec[0])
     }
 
+    /// Same as `from_geojson_vec`, but returns an error instead of panicking if the position has
+    /// less than two values or they are out of range, e.g. for positions of a file.
+    pub fn try_from_geojson_vec(vec: &[f64]) -> Result<Point> {
+        match vec {
+            [longitude, latitude, ..] => Point::try_from_coordinate(*latitude, *longitude),
+            _ => Err(Error::InvalidArgument(format!(
+                "the position {:?} has no longitude and latitude",
+                vec
+            ))),
+        }
+    }
+
     pub fn to_feature(&self) -> Feature {
         let point: Vec<f64> = self.to_geojson_vec();
         let point = Geometry::new(Value::Point(point));
@@ -189,12 +215,25 @@ pub fn radians_to_meter(radians: f64) -> f64 {
     radians * (EARTH_CIRCUMFERENCE_METERS / (2.0 * PI))
 }
 
+pub const METERS_PER_NAUTICAL_MILE: f64 = 1_852.0;
+
 #[cfg(test)]
 mod tests {
     use std::f64::consts::PI;
 
     use crate::geometry::{meters_to_radians, radians_to_meter, Point};
 
+    #[test]
+    fn try_from_coordinate_rejects_out_of_range() {
+        assert!(Point::try_from_coordinate(45.0, -170.0).is_ok());
+        assert!(Point::try_from_coordinate(90.5, 0.0).is_err());
+        assert!(Point::try_from_coordinate(0.0, 181.0).is_err());
+        assert!(Point::try_from_coordinate(f64::NAN, 0.0).is_err());
+        assert!(Point::try_from_geojson_vec(&[-170.0, 45.0]).is_ok());
+        assert!(Point::try_from_geojson_vec(&[0.0, 91.0]).is_err());
+        assert!(Point::try_from_geojson_vec(&[0.0]).is_err());
+    }
+
     #[test]
     fn conversion_between_n_vector_and_coordinates() {
         for _ in 0..100 {
@@ -2,7 +2,11 @@ use std::vec;
 
 use geojson::{Feature, Geometry, Value};
 
-use super::{Arc, Point};
+use nalgebra::Vector3;
+
+use crate::Result;
+
+use super::{geojson_outlines, radians_to_meter, read_geojson_features, Arc, Contains, Point};
 
 #[derive(Clone)]
 pub struct Polygon {
@@ -31,6 +35,57 @@ impl Polygon {
             .collect()
     }
 
+    /// Returns the center of the polygon, the normalized mean of its outline, and the distance of
+    /// the farthest outline point from it in meters. The polygon has to be smaller than a
+    /// hemisphere.
+    pub fn bounding_circle(&self) -> (Point, f64) {
+        let sum: Vector3<f64> = self.outline.iter().map(|point| point.n_vector()).sum();
+        let center = Point::from_n_vector(&sum.normalize());
+        let radius = self
+            .outline
+            .iter()
+            .map(|point| radians_to_meter(Arc::new(&center, point).central_angle()))
+            .fold(0.0, f64::max);
+        (center, radius)
+    }
+
+    /// Returns how many meters of the arc are inside the polygon.
+    pub fn length_inside(&self, arc: &Arc) -> f64 {
+        let mut crossings: Vec<f64> = self
+            .intersections(arc)
+            .iter()
+            .map(|point| Arc::new(arc.from(), point).central_angle())
+            .collect();
+        crossings.sort_by(f64::total_cmp);
+        crossings.push(arc.central_angle());
+
+        // the arc alternates between inside and outside at every crossing
+        let mut is_inside = self.contains(arc.from());
+        let mut last_crossing = 0.0;
+        let mut inside = 0.0;
+        for crossing in crossings {
+            if is_inside {
+                inside += crossing - last_crossing;
+            }
+            is_inside = !is_inside;
+            last_crossing = crossing;
+        }
+        radians_to_meter(inside)
+    }
+
+    /// Reads the Polygons and MultiPolygons of a GeoJSON file, e.g. of areas to avoid. Holes of
+    /// the polygons are ignored and other geometries are skipped.
+    pub fn from_geojson_file(path: &str) -> Result<Vec<Polygon>> {
+        let mut polygons = Vec::new();
+        for feature in read_geojson_features(path)? {
+            if let Some(geometry) = feature.geometry {
+                let outlines = geojson_outlines(path, &geometry.value)?;
+                polygons.extend(outlines.into_iter().map(Polygon::new));
+            }
+        }
+        Ok(polygons)
+    }
+
     pub fn from_geojson_vec(vec: Vec<Vec<f64>>) -> Polygon {
         let outline = vec
             .into_iter()
@@ -62,3 +117,40 @@ impl Polygon {
         self.to_feature().to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::{radians_to_meter, Arc, Point, Polygon};
+
+    #[test]
+    fn length_inside_counts_the_parts_within() {
+        let point = |lat, lon| Point::from_coordinate(lat, lon);
+        let polygon = Polygon::new(vec![
+            point(10.0, 20.0),
+            point(10.0, 21.0),
+            point(11.0, 21.0),
+            point(11.0, 20.0),
+            point(10.0, 20.0),
+        ]);
+        let length =
+            |from: &Point, to: &Point| radians_to_meter(Arc::new(from, to).central_angle());
+        let is_close = |a: f64, b: f64| (a - b).abs() < 1.0;
+
+        let across = Arc::new(&point(10.5, 19.5), &point(10.5, 21.5));
+        let inside = length(&point(10.5, 20.0), &point(10.5, 21.0));
+        assert!(is_close(polygon.length_inside(&across), inside));
+
+        let leaving = Arc::new(&point(10.5, 20.5), &point(10.5, 21.5));
+        let inside = length(&point(10.5, 20.5), &point(10.5, 21.0));
+        assert!(is_close(polygon.length_inside(&leaving), inside));
+
+        let within = Arc::new(&point(10.4, 20.4), &point(10.6, 20.6));
+        assert!(is_close(
+            polygon.length_inside(&within),
+            length(within.from(), within.to())
+        ));
+
+        let outside = Arc::new(&point(12.0, 19.5), &point(12.0, 21.5));
+        assert_eq!(polygon.length_inside(&outside), 0.0);
+    }
+}
@@ -0,0 +1,145 @@
+use crate::{
+    geometry::{grid::Grid, Point},
+    text_reader::TextReader,
+    Error, Result,
+};
+
+/// Vectors on a grid that change over time, e.g. ocean currents or wind, as east and north
+/// components in meters per second. Between two time steps the vectors are interpolated, before
+/// the first and after the last step they stay the same.
+pub struct VectorField {
+    grid: Grid,
+    /// Unix time of each step in seconds, increasing.
+    times: Vec<f64>,
+    /// East and north component of each cell per step, row by row from north to south.
+    steps: Vec<Vec<f32>>,
+}
+
+impl VectorField {
+    /// Reads a field in the ESRI ASCII format with one block per time step. After the header of
+    /// the grid, each block starts with a line `time <unix seconds>`, followed by the rows from
+    /// north to south, each with the east and north component of every cell:
+    ///
+    /// ```text
+    /// ncols 2
+    /// nrows 1
+    /// xllcorner 20
+    /// yllcorner 10
+    /// cellsize 0.5
+    /// NODATA_value -9999
+    /// time 1700000000
+    /// 0.5 0.1 -9999 -9999
+    /// time 1700021600
+    /// 0.4 0.2 -0.1 0.0
+    /// ```
+    ///
+    /// Cells without data have no current or wind. Grids like NetCDF files can be exported to
+    /// this format step by step.
+    pub fn from_file(path: &str) -> Result<VectorField> {
+        let mut reader = TextReader::open(path)?;
+        let (grid, mut line) = Grid::read_header(&mut reader, path)?;
+
+        let mut times = Vec::new();
+        let mut steps = Vec::new();
+        loop {
+            let mut values = line.split_whitespace();
+            if values.next() != Some("time") {
+                return Err(Error::format(path, "expected 'time' before each step"));
+            }
+            let time: f64 = reader.parse(values.next())?;
+            if times.last().is_some_and(|&last| time <= last) {
+                return Err(Error::format(
+                    path,
+                    format!("time {} is not after the step before", time),
+                ));
+            }
+            times.push(time);
+            let first_row = reader.next_line()?;
+            steps.push(grid.read_rows(&mut reader, path, first_row, 2)?);
+
+            // empty lines between and after the steps are skipped
+            let next = loop {
+                match reader.try_next_line()? {
+                    Some(line) if line.trim().is_empty() => continue,
+                    next => break next,
+                }
+            };
+            match next {
+                Some(next) => line = next,
+                None => break,
+            }
+        }
+        println!(
+            "read {} steps of {}x{} vectors",
+            steps.len(),
+            grid.num_columns,
+            grid.num_rows
+        );
+
+        Ok(VectorField { grid, times, steps })
+    }
+
+    /// Returns the east and north component at the point and unix time in meters per second,
+    /// zero outside of the grid.
+    pub fn at(&self, point: &Point, time: f64) -> (f64, f64) {
+        let Some(index) = self.grid.index(point) else {
+            return (0.0, 0.0);
+        };
+        let vector = |step: usize| {
+            let (east, north) = (self.steps[step][2 * index], self.steps[step][2 * index + 1]);
+            if east.is_nan() || north.is_nan() {
+                (0.0, 0.0)
+            } else {
+                (east as f64, north as f64)
+            }
+        };
+
+        let next = self.times.partition_point(|&step_time| step_time <= time);
+        if next == 0 {
+            return vector(0);
+        }
+        if next == self.times.len() {
+            return vector(next - 1);
+        }
+        let f = (time - self.times[next - 1]) / (self.times[next] - self.times[next - 1]);
+        let (before, after) = (vector(next - 1), vector(next));
+        (
+            before.0 * (1.0 - f) + after.0 * f,
+            before.1 * (1.0 - f) + after.1 * f,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        geometry::{Point, VectorField},
+        TestFile,
+    };
+
+    #[test]
+    fn vectors_are_interpolated_in_time() {
+        let file = TestFile::new(
+            "vector_field.asc",
+            "ncols 2\nnrows 1\nxllcorner 20\nyllcorner 10\ncellsize 0.5\nNODATA_value -9999\n\
+             time 1000\n\
+             1 0 -9999 -9999\n\
+             \n\
+             time 2000\n\
+             3 -2 0.5 0.5\n",
+        );
+        let field = VectorField::from_file(file.path()).unwrap();
+
+        let west = Point::from_coordinate(10.25, 20.25);
+        let east = Point::from_coordinate(10.25, 20.75);
+        assert_eq!(field.at(&west, 0.0), (1.0, 0.0));
+        assert_eq!(field.at(&west, 1_500.0), (2.0, -1.0));
+        assert_eq!(field.at(&west, 5_000.0), (3.0, -2.0));
+        assert_eq!(field.at(&east, 1_000.0), (0.0, 0.0));
+        assert_eq!(field.at(&east, 2_000.0), (0.5, 0.5));
+        assert_eq!(
+            field.at(&Point::from_coordinate(0.0, 0.0), 1_500.0),
+            (0.0, 0.0)
+        );
+    }
+}
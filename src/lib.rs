@@ -1,4 +1,12 @@
+mod error;
 pub mod geometry;
 pub mod routing;
 pub mod spatial_graph;
 pub mod spatial_partition;
+#[cfg(test)]
+mod test_file;
+mod text_reader;
+
+pub use error::*;
+#[cfg(test)]
+pub(crate) use test_file::*;
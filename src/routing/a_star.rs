@@ -0,0 +1,176 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use crate::geometry::{radians_to_meter, Arc, Point};
+
+use super::Graph;
+
+/// Gives a lower bound of the remaining cost to the target, which A* uses to direct the search.
+pub trait Heuristic {
+    /// Returns a lower bound of the cost from `node_id` to `target_id`. It must never exceed the
+    /// real cost, otherwise the found route may not be the shortest.
+    fn lower_bound(&self, node_id: u32, target_id: u32) -> u32;
+}
+
+/// The great circle distance between two nodes, scaled to the edge costs. As the edge costs are
+/// great circle distances in meters as well, no path can be much shorter, see `new`.
+#[derive(Clone)]
+pub struct GreatCircle<'a> {
+    graph: &'a Graph,
+    /// The least ratio of cost to length of any edge.
+    cost_per_meter: f64,
+}
+
+impl<'a> GreatCircle<'a> {
+    /// `Graph::from_arcs` truncates the length of each edge to whole meters, which loses up to a
+    /// meter per edge, so a path of many short edges can cost much less than its length and no
+    /// fixed slack is enough. Instead, with the least ratio `r` of cost to length of any edge,
+    /// every edge costs at least `r` times its length, so by the triangle inequality every path
+    /// costs at least `r` times the great circle distance between its ends. This also holds if
+    /// the coordinates were rounded after the costs were computed, e.g. in a `.fmi` file. Edges
+    /// without length are skipped, they cost at least their length of zero.
+    ///
+    /// The lengths are computed in f64 from the n-vectors, for edges longer than a few meters
+    /// their error is far below a millimeter. One meter is subtracted from the bound for it.
+    pub fn new(graph: &'a Graph) -> GreatCircle<'a> {
+        let cost_per_meter = (0..graph.edges.len() as u32)
+            .filter_map(|edge_id| {
+                let meters = radians_to_meter(graph.edge_arc(edge_id).central_angle());
+                (meters > 0.0).then(|| graph.edges[edge_id as usize].cost as f64 / meters)
+            })
+            .fold(f64::INFINITY, f64::min);
+        GreatCircle {
+            graph,
+            // without edges of any length, only nodes at the same position are connected
+            cost_per_meter: if cost_per_meter.is_finite() {
+                cost_per_meter
+            } else {
+                0.0
+            },
+        }
+    }
+
+    fn point(&self, node_id: u32) -> &Point {
+        &self.graph.nodes[node_id as usize]
+    }
+}
+
+impl Heuristic for GreatCircle<'_> {
+    fn lower_bound(&self, node_id: u32, target_id: u32) -> u32 {
+        let meters =
+            radians_to_meter(Arc::new(self.point(node_id), self.point(target_id)).central_angle());
+        ((meters * self.cost_per_meter) as u32).saturating_sub(1)
+    }
+}
+
+#[derive(Clone)]
+pub struct AStar<'a, H: Heuristic> {
+    graph: &'a Graph,
+    heuristic: H,
+}
+
+impl<'a, H: Heuristic> AStar<'a, H> {
+    pub fn new(graph: &'a Graph, heuristic: H) -> AStar<'a, H> {
+        AStar { graph, heuristic }
+    }
+
+    /// Same as `Dijkstra::dijkstra`, but settles nodes in order of their cost plus the lower bound
+    /// given by the heuristic. Nodes are reopened if a cheaper path to them is found, so the
+    /// heuristic only needs to be admissible, not consistent.
+    pub fn a_star(&self, from_node_id: u32, to_node_id: u32) -> (Vec<Option<u32>>, u32) {
+        let mut queue = BinaryHeap::new();
+
+        let mut edge_from_predecessor = vec![None; self.graph.nodes.len()];
+        let mut node_cost = vec![u32::MAX; self.graph.nodes.len()];
+
+        node_cost[from_node_id as usize] = 0;
+        queue.push(Reverse((
+            self.heuristic.lower_bound(from_node_id, to_node_id),
+            0,
+            from_node_id,
+        )));
+
+        while let Some(Reverse((_, cost, node_id))) = queue.pop() {
+            if cost > node_cost[node_id as usize] {
+                continue;
+            }
+            if node_id == to_node_id {
+                break;
+            }
+
+            (self.graph.edges_start_at[node_id as usize]
+                ..self.graph.edges_start_at[node_id as usize + 1])
+                .for_each(|edge_id| {
+                    let edge = &self.graph.edges[edge_id as usize];
+                    let alternative_cost = cost + edge.cost;
+                    if alternative_cost < node_cost[edge.target_id as usize] {
+                        edge_from_predecessor[edge.target_id as usize] = Some(edge_id);
+                        node_cost[edge.target_id as usize] = alternative_cost;
+                        let key = alternative_cost
+                            + self.heuristic.lower_bound(edge.target_id, to_node_id);
+                        queue.push(Reverse((key, alternative_cost, edge.target_id)));
+                    }
+                });
+        }
+
+        (edge_from_predecessor, node_cost[to_node_id as usize])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        geometry::{Arc, Point},
+        routing::{
+            assert_same_cost_as_dijkstra, edge_case_graph, get_route, random_graph, AStar,
+            Dijkstra, Graph, GreatCircle, Heuristic,
+        },
+    };
+
+    #[test]
+    fn same_cost_as_dijkstra() {
+        for graph in [random_graph(1_000, 3_000), edge_case_graph()] {
+            let a_star = AStar::new(&graph, GreatCircle::new(&graph));
+            assert_same_cost_as_dijkstra(&graph, |from, to| {
+                let (used_edges, cost) = a_star.a_star(from, to);
+                (cost, get_route(&graph, from, to, used_edges))
+            });
+        }
+    }
+
+    #[test]
+    fn lower_bound_is_admissible_on_truncated_costs() {
+        // edges of about 1.9 meters each cost 1 meter, the path costs half its length
+        let points: Vec<Point> = (0..500)
+            .map(|i| Point::from_coordinate(10.0, 20.0 + i as f64 * 1.7e-5))
+            .collect();
+        let arcs: Vec<Arc> = points
+            .windows(2)
+            .flat_map(|pair| [Arc::new(&pair[0], &pair[1]), Arc::new(&pair[1], &pair[0])])
+            .collect();
+        let graph = Graph::from_arcs(points, &arcs);
+        assert!(graph.edges.iter().all(|edge| edge.cost == 1));
+        let great_circle = GreatCircle::new(&graph);
+        let dijkstra = Dijkstra::new(&graph);
+        for to in (0..500).step_by(7) {
+            let (_, cost) = dijkstra.dijkstra(0, to);
+            assert!(
+                great_circle.lower_bound(0, to) <= cost,
+                "not admissible to {}",
+                to
+            );
+        }
+        assert!(great_circle.lower_bound(0, 499) >= 490);
+
+        let graph = random_graph(1_000, 3_000);
+        let great_circle = GreatCircle::new(&graph);
+        let dijkstra = Dijkstra::new(&graph);
+        for to in 0..1_000 {
+            let (_, cost) = dijkstra.dijkstra(0, to);
+            assert!(
+                great_circle.lower_bound(0, to) <= cost,
+                "not admissible to {}",
+                to
+            );
+        }
+    }
+}
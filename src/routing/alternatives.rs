@@ -0,0 +1,152 @@
+use std::collections::{HashMap, HashSet};
+
+use super::{Dijkstra, DijkstraWorkspace, Graph, Route};
+
+/// Finds meaningfully different routes between two nodes with the penalty method. After each
+/// query the edges of the found route get more expensive, so the next query tends to avoid them.
+/// A found route is only kept if it is not much longer than the shortest route (stretch) and does
+/// not share too much of its length with any route kept so far (sharing).
+pub struct AlternativeRoutes<'a> {
+    graph: &'a Graph,
+    /// Each time an edge is part of a found route its cost is raised by this factor of its
+    /// original cost.
+    pub penalty: f64,
+    /// The maximal part of the cost of an alternative that may be shared with another route.
+    pub max_sharing: f64,
+    /// The maximal cost of an alternative, as a factor of the cost of the shortest route.
+    pub max_stretch: f64,
+    /// The maximal number of queries per requested route.
+    pub max_queries_per_route: usize,
+}
+
+impl<'a> AlternativeRoutes<'a> {
+    pub fn new(graph: &'a Graph) -> AlternativeRoutes<'a> {
+        AlternativeRoutes {
+            graph,
+            penalty: 0.5,
+            max_sharing: 0.75,
+            max_stretch: 1.4,
+            max_queries_per_route: 5,
+        }
+    }
+
+    /// Returns up to `num_routes` routes from `from_node_id` to `to_node_id`, the shortest route
+    /// first. The cost of each route is its original cost without penalties. Returns no route if
+    /// `to_node_id` is not reachable. The queries run in the workspace, e.g. one reused for many
+    /// requests.
+    pub fn routes(
+        &self,
+        workspace: &mut DijkstraWorkspace,
+        from_node_id: u32,
+        to_node_id: u32,
+        num_routes: usize,
+    ) -> Vec<Route> {
+        let dijkstra = Dijkstra::new(self.graph);
+        // how often each edge, given by its source and target, was part of a found route
+        let mut num_penalties: HashMap<(u32, u32), u32> = HashMap::new();
+        let mut routes: Vec<Route> = Vec::new();
+        let mut route_edges: Vec<HashSet<(u32, u32)>> = Vec::new();
+
+        for _ in 0..num_routes * self.max_queries_per_route {
+            if routes.len() >= num_routes {
+                break;
+            }
+            dijkstra.query_with_weights(workspace, from_node_id, to_node_id, |_, edge| {
+                let count = num_penalties
+                    .get(&(edge.source_id, edge.target_id))
+                    .copied()
+                    .unwrap_or(0);
+                let weight = edge.cost as f64 * (1.0 + self.penalty * count as f64);
+                Some(weight.min(u32::MAX as f64) as u32)
+            });
+            let Some(route) = workspace.route(self.graph, from_node_id, to_node_id) else {
+                break;
+            };
+
+            let edges: HashSet<(u32, u32)> = route
+                .edges
+                .iter()
+                .map(|edge| (edge.source_id, edge.target_id))
+                .collect();
+            for &edge in edges.iter() {
+                *num_penalties.entry(edge).or_insert(0) += 1;
+            }
+
+            // a route of zero cost shares nothing, so repeated routes have to be rejected directly
+            if !route_edges.contains(&edges) && self.is_admissible(&route, &routes, &route_edges) {
+                routes.push(route);
+                route_edges.push(edges);
+            }
+        }
+
+        routes
+    }
+
+    fn is_admissible(
+        &self,
+        route: &Route,
+        routes: &[Route],
+        route_edges: &[HashSet<(u32, u32)>],
+    ) -> bool {
+        let Some(shortest) = routes.first() else {
+            return true;
+        };
+        if route.cost as f64 > shortest.cost as f64 * self.max_stretch {
+            return false;
+        }
+
+        route_edges.iter().all(|other_edges| {
+            let shared_cost: u64 = route
+                .edges
+                .iter()
+                .filter(|edge| other_edges.contains(&(edge.source_id, edge.target_id)))
+                .map(|edge| edge.cost as u64)
+                .sum();
+            shared_cost as f64 <= route.cost as f64 * self.max_sharing
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use crate::routing::{graph::random_graph, AlternativeRoutes, Dijkstra, DijkstraWorkspace};
+
+    #[test]
+    fn alternatives_respect_limits() {
+        let graph = random_graph(1_000, 5_000);
+        let alternatives = AlternativeRoutes::new(&graph);
+        let dijkstra = Dijkstra::new(&graph);
+        let mut workspace = DijkstraWorkspace::new(graph.nodes.len());
+
+        let mut rng = StdRng::seed_from_u64(10);
+        for _ in 0..50 {
+            let from = rng.gen_range(0..1_000);
+            let to = rng.gen_range(0..1_000);
+            let routes = alternatives.routes(&mut workspace, from, to, 3);
+            let (_, expected) = dijkstra.dijkstra(from, to);
+            if expected == u32::MAX {
+                assert!(routes.is_empty());
+                continue;
+            }
+
+            assert!(!routes.is_empty() && routes.len() <= 3);
+            assert_eq!(
+                routes[0].cost, expected,
+                "wrong cost from {} to {}",
+                from, to
+            );
+            for route in routes.iter() {
+                let node_ids = route.node_ids();
+                assert_eq!(node_ids.first(), Some(&from));
+                assert_eq!(node_ids.last(), Some(&to));
+                assert!(route.cost as f64 <= expected as f64 * alternatives.max_stretch);
+                assert_eq!(
+                    route.cost,
+                    route.edges.iter().map(|edge| edge.cost).sum::<u32>()
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use std::collections::HashSet;
+
+use crate::geometry::{Arc, Collides, Polygon};
+
+use super::Graph;
+
+/// Areas that routes keep out of, e.g. high risk areas, military exercise zones or marine
+/// protected areas. The edges colliding with an area are found once, so the graph does not have
+/// to be generated again for other areas.
+pub struct AvoidAreas {
+    polygons: Vec<Polygon>,
+    edge_ids: HashSet<u32>,
+    /// If set, the edges in the areas cost this factor of their cost instead of being blocked, so
+    /// routes only cross the areas where going around costs even more.
+    pub penalty: Option<f64>,
+}
+
+impl AvoidAreas {
+    /// Finds the edges colliding with any of the polygons. A polygon has to be smaller than a
+    /// hemisphere.
+    pub fn new(graph: &Graph, polygons: &[Polygon]) -> AvoidAreas {
+        let edge_ids = polygons
+            .iter()
+            .flat_map(|polygon| graph.colliding_edge_ids(polygon))
+            .collect();
+
+        AvoidAreas {
+            polygons: polygons.to_vec(),
+            edge_ids,
+            penalty: None,
+        }
+    }
+
+    /// Returns true if the edge collides with any of the areas.
+    pub fn contains(&self, edge_id: u32) -> bool {
+        self.edge_ids.contains(&edge_id)
+    }
+
+    /// Returns the cost of the edge for `Dijkstra::query_with_weights`, which is blocked or
+    /// penalized in the areas, given its cost outside, e.g. `edge.cost`.
+    pub fn weight(&self, edge_id: u32, cost: u32) -> Option<u32> {
+        if !self.contains(edge_id) {
+            return Some(cost);
+        }
+        self.penalized(cost)
+    }
+
+    /// Same as `weight` for an arc that is not an edge of the graph, e.g. the connection of a
+    /// `VirtualNode`.
+    pub fn arc_weight(&self, arc: &Arc, cost: u32) -> Option<u32> {
+        if !self.polygons.iter().any(|polygon| arc.collides(polygon)) {
+            return Some(cost);
+        }
+        self.penalized(cost)
+    }
+
+    fn penalized(&self, cost: u32) -> Option<u32> {
+        self.penalty
+            .map(|penalty| (cost as f64 * penalty).min(u32::MAX as f64) as u32)
+    }
+
+    /// Returns the graph without the edges in the areas, e.g. for areas that are avoided by all
+    /// queries.
+    pub fn remove_from(&self, graph: &Graph) -> Graph {
+        graph.filter_edges(|edge_id| !self.contains(edge_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use crate::{
+        geometry::{Collides, Point, Polygon},
+        routing::{
+            detour_area, detour_graph, random_graph, AvoidAreas, Dijkstra, DijkstraWorkspace,
+        },
+    };
+
+    fn area() -> Polygon {
+        let point = |lat, lon| Point::from_coordinate(lat, lon);
+        Polygon::new(vec![
+            point(10.05, 20.05),
+            point(10.05, 20.12),
+            point(10.12, 20.12),
+            point(10.12, 20.05),
+            point(10.05, 20.05),
+        ])
+    }
+
+    #[test]
+    fn finds_all_edges_in_areas() {
+        let graph = random_graph(1_000, 5_000);
+        let area = area();
+        let avoid_areas = AvoidAreas::new(&graph, std::slice::from_ref(&area));
+        for edge_id in 0..graph.edges.len() as u32 {
+            let arc = graph.edge_arc(edge_id);
+            assert_eq!(avoid_areas.contains(edge_id), arc.collides(&area));
+            assert_eq!(
+                avoid_areas.arc_weight(&arc, 1),
+                avoid_areas.weight(edge_id, 1)
+            );
+        }
+    }
+
+    #[test]
+    fn blocked_areas_match_removed_edges() {
+        let graph = random_graph(1_000, 5_000);
+        let avoid_areas = AvoidAreas::new(&graph, &[area()]);
+        let removed = avoid_areas.remove_from(&graph);
+        let dijkstra = Dijkstra::new(&graph);
+        let removed_dijkstra = Dijkstra::new(&removed);
+        let mut workspace = DijkstraWorkspace::new(graph.nodes.len());
+
+        let mut rng = StdRng::seed_from_u64(21);
+        for _ in 0..20 {
+            let from = rng.gen_range(0..1_000);
+            let to = rng.gen_range(0..1_000);
+            let cost = dijkstra.query_with_weights(&mut workspace, from, to, |edge_id, edge| {
+                avoid_areas.weight(edge_id, edge.cost)
+            });
+            assert_eq!(cost, removed_dijkstra.dijkstra(from, to).1);
+        }
+    }
+
+    #[test]
+    fn penalized_areas_are_crossed_if_needed() {
+        let graph = random_graph(1_000, 5_000);
+        let mut avoid_areas = AvoidAreas::new(&graph, &[area()]);
+        avoid_areas.penalty = Some(10.0);
+        let dijkstra = Dijkstra::new(&graph);
+        let mut workspace = DijkstraWorkspace::new(graph.nodes.len());
+
+        let mut rng = StdRng::seed_from_u64(21);
+        for _ in 0..20 {
+            let from = rng.gen_range(0..1_000);
+            let to = rng.gen_range(0..1_000);
+            let cost = dijkstra.query_with_weights(&mut workspace, from, to, |edge_id, edge| {
+                avoid_areas.weight(edge_id, edge.cost)
+            });
+            // a penalty never blocks a route
+            assert_eq!(cost == u32::MAX, dijkstra.dijkstra(from, to).1 == u32::MAX);
+        }
+    }
+
+    #[test]
+    fn avoided_areas_on_a_known_graph() {
+        let graph = detour_graph();
+        let mut avoid_areas = AvoidAreas::new(&graph, &[detour_area()]);
+        let dijkstra = Dijkstra::new(&graph);
+        let mut workspace = DijkstraWorkspace::new(graph.nodes.len());
+
+        let mut route = |avoid_areas: &AvoidAreas| {
+            dijkstra.query_with_weights(&mut workspace, 0, 2, |edge_id, edge| {
+                avoid_areas.weight(edge_id, edge.cost)
+            });
+            workspace.route(&graph, 0, 2).unwrap().node_ids()
+        };
+        assert_eq!(route(&avoid_areas), vec![0, 3, 2]);
+        // the detour is about 1.4 times as long
+        avoid_areas.penalty = Some(1.2);
+        assert_eq!(route(&avoid_areas), vec![0, 1, 2]);
+    }
+}
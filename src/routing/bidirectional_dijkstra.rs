@@ -0,0 +1,155 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use super::Graph;
+
+#[derive(Clone)]
+pub struct BidirectionalDijkstra<'a> {
+    graph: &'a Graph,
+}
+
+/// One direction of the bidirectional search. The backward search runs on the reverse adjacency
+/// array, so `edge_to_next` holds the edge leading towards the start of the respective search.
+struct Search {
+    node_cost: Vec<u32>,
+    edge_to_next: Vec<Option<u32>>,
+    queue: BinaryHeap<Reverse<(u32, u32)>>,
+}
+
+impl Search {
+    fn new(num_nodes: usize, start_node_id: u32) -> Search {
+        let mut node_cost = vec![u32::MAX; num_nodes];
+        node_cost[start_node_id as usize] = 0;
+        Search {
+            node_cost,
+            edge_to_next: vec![None; num_nodes],
+            queue: BinaryHeap::from(vec![Reverse((0, start_node_id))]),
+        }
+    }
+
+    /// Returns the smallest key in the queue, skipping entries that are outdated.
+    fn min_key(&mut self) -> Option<u32> {
+        while let Some(&Reverse((cost, node_id))) = self.queue.peek() {
+            if cost == self.node_cost[node_id as usize] {
+                return Some(cost);
+            }
+            self.queue.pop();
+        }
+        None
+    }
+}
+
+impl<'a> BidirectionalDijkstra<'a> {
+    pub fn new(graph: &'a Graph) -> BidirectionalDijkstra<'a> {
+        BidirectionalDijkstra { graph }
+    }
+
+    /// Searches forward from the source and backward from the target, always expanding the
+    /// direction with the smaller key. The search stops as soon as the sum of both smallest keys
+    /// is at least the cost of the best path seen so far. Returns the ids of the edges of the path
+    /// in order and its cost, e.g. for `get_route_of_edges`. No edges and `u32::MAX` if the target
+    /// is not reachable.
+    pub fn dijkstra(&self, from_node_id: u32, to_node_id: u32) -> (Vec<u32>, u32) {
+        let num_nodes = self.graph.nodes.len();
+        let mut forward = Search::new(num_nodes, from_node_id);
+        let mut backward = Search::new(num_nodes, to_node_id);
+
+        let mut best_cost = u32::MAX;
+        let mut meeting_node_id = None;
+        if from_node_id == to_node_id {
+            best_cost = 0;
+            meeting_node_id = Some(from_node_id);
+        }
+
+        while let (Some(forward_key), Some(backward_key)) = (forward.min_key(), backward.min_key())
+        {
+            if forward_key.saturating_add(backward_key) >= best_cost {
+                break;
+            }
+
+            if forward_key <= backward_key {
+                let Some(Reverse((cost, node_id))) = forward.queue.pop() else {
+                    break;
+                };
+                for edge_id in self.graph.outgoing_edge_ids(node_id) {
+                    let edge = &self.graph.edges[edge_id as usize];
+                    let alternative_cost = cost + edge.cost;
+                    if alternative_cost < forward.node_cost[edge.target_id as usize] {
+                        forward.node_cost[edge.target_id as usize] = alternative_cost;
+                        forward.edge_to_next[edge.target_id as usize] = Some(edge_id);
+                        forward
+                            .queue
+                            .push(Reverse((alternative_cost, edge.target_id)));
+
+                        let backward_cost = backward.node_cost[edge.target_id as usize];
+                        if alternative_cost.saturating_add(backward_cost) < best_cost {
+                            best_cost = alternative_cost + backward_cost;
+                            meeting_node_id = Some(edge.target_id);
+                        }
+                    }
+                }
+            } else {
+                let Some(Reverse((cost, node_id))) = backward.queue.pop() else {
+                    break;
+                };
+                for &edge_id in self.graph.incoming_edge_ids(node_id) {
+                    let edge = &self.graph.edges[edge_id as usize];
+                    let alternative_cost = cost + edge.cost;
+                    if alternative_cost < backward.node_cost[edge.source_id as usize] {
+                        backward.node_cost[edge.source_id as usize] = alternative_cost;
+                        backward.edge_to_next[edge.source_id as usize] = Some(edge_id);
+                        backward
+                            .queue
+                            .push(Reverse((alternative_cost, edge.source_id)));
+
+                        let forward_cost = forward.node_cost[edge.source_id as usize];
+                        if alternative_cost.saturating_add(forward_cost) < best_cost {
+                            best_cost = alternative_cost + forward_cost;
+                            meeting_node_id = Some(edge.source_id);
+                        }
+                    }
+                }
+            }
+        }
+
+        let Some(meeting_node_id) = meeting_node_id else {
+            return (Vec::new(), u32::MAX);
+        };
+        // the halves are collected from each search tree separately, as mixing the trees could
+        // form a cycle over edges of zero cost
+        let mut edge_ids = Vec::new();
+        let mut current = meeting_node_id;
+        while let Some(edge_id) = forward.edge_to_next[current as usize] {
+            edge_ids.push(edge_id);
+            current = self.graph.edges[edge_id as usize].source_id;
+        }
+        edge_ids.reverse();
+        let mut current = meeting_node_id;
+        while let Some(edge_id) = backward.edge_to_next[current as usize] {
+            edge_ids.push(edge_id);
+            current = self.graph.edges[edge_id as usize].target_id;
+        }
+
+        (edge_ids, best_cost)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::routing::{
+        assert_same_cost_as_dijkstra, edge_case_graph, get_route_of_edges, random_graph,
+        BidirectionalDijkstra,
+    };
+
+    #[test]
+    fn same_cost_as_dijkstra() {
+        for graph in [random_graph(1_000, 3_000), edge_case_graph()] {
+            let bidirectional_dijkstra = BidirectionalDijkstra::new(&graph);
+            assert_same_cost_as_dijkstra(&graph, |from, to| {
+                let (edge_ids, cost) = bidirectional_dijkstra.dijkstra(from, to);
+                let route =
+                    (cost != u32::MAX).then(|| get_route_of_edges(&graph, from, to, &edge_ids));
+                (cost, route)
+            });
+        }
+    }
+}
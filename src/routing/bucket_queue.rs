@@ -1,3 +1,20 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+/// A priority queue for `Dijkstra`, which pops values in increasing order of their keys. It is
+/// monotone: a key may never be smaller than the key that was popped last. A key is decreased by
+/// inserting the value again with the smaller key. The `RadixHeap` moves the entry, the other
+/// queues keep the outdated entry, which is still popped later and has to be skipped by the
+/// caller, like `Dijkstra` does for expanded nodes.
+pub trait MonotoneQueue {
+    fn insert(&mut self, key: u32, value: u32);
+    fn pop(&mut self) -> Option<u32>;
+    fn clear(&mut self);
+}
+
+/// A cyclic bucket queue with one bucket per key. Keys are taken modulo the number of buckets, so
+/// the ordering is only correct as long as all keys in the queue differ by less than `max_diff`,
+/// e.g. if no edge costs more than `max_diff`. Prefer the `RadixHeap`, which is correct for any
+/// keys and is the default queue of `DijkstraWorkspace`.
 pub struct BucketQueue {
     current_index: usize,
     buckets: Vec<Vec<u32>>,
@@ -26,4 +43,251 @@ impl BucketQueue {
         }
         None
     }
+
+    pub fn clear(&mut self) {
+        self.buckets.iter_mut().for_each(|bucket| bucket.clear());
+        self.current_index = 0;
+    }
+}
+
+impl MonotoneQueue for BucketQueue {
+    fn insert(&mut self, key: u32, value: u32) {
+        self.insert(key, value)
+    }
+
+    fn pop(&mut self) -> Option<u32> {
+        self.pop()
+    }
+
+    fn clear(&mut self) {
+        self.clear()
+    }
+}
+
+/// A radix heap, which is correct for arbitrary keys. Bucket `i` holds the entries whose key
+/// first differs from the last popped key in bit `i - 1`, bucket 0 the entries with exactly the
+/// last popped key. Popping from an empty bucket 0 redistributes the first non-empty bucket, and
+/// as every entry can only move to lower buckets, each entry is moved at most 32 times.
+///
+/// Every value is in the heap at most once: inserting a value again with a smaller key moves its
+/// entry, a larger key is ignored. The values are used as indices, e.g. node ids, so they should
+/// be small.
+pub struct RadixHeap {
+    last_key: u32,
+    len: usize,
+    buckets: Vec<Vec<(u32, u32)>>,
+    /// The bucket and the index in the bucket of each value, `NOT_IN_HEAP` if it is not in the
+    /// heap.
+    positions: Vec<(u32, u32)>,
+}
+
+const NOT_IN_HEAP: (u32, u32) = (u32::MAX, u32::MAX);
+
+impl RadixHeap {
+    pub fn new() -> RadixHeap {
+        RadixHeap {
+            last_key: 0,
+            len: 0,
+            buckets: vec![Vec::new(); 33],
+            positions: Vec::new(),
+        }
+    }
+
+    /// Returns the key of the value, `None` if it is not in the heap.
+    pub fn key(&self, value: u32) -> Option<u32> {
+        let &(bucket_index, index) = self.positions.get(value as usize)?;
+        (bucket_index != NOT_IN_HEAP.0)
+            .then(|| self.buckets[bucket_index as usize][index as usize].0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn bucket_index(&self, key: u32) -> usize {
+        32 - (key ^ self.last_key).leading_zeros() as usize
+    }
+
+    fn push(&mut self, key: u32, value: u32) {
+        let bucket_index = self.bucket_index(key);
+        let bucket = &mut self.buckets[bucket_index];
+        self.positions[value as usize] = (bucket_index as u32, bucket.len() as u32);
+        bucket.push((key, value));
+    }
+
+    fn remove(&mut self, value: u32) {
+        let (bucket_index, index) = self.positions[value as usize];
+        let bucket = &mut self.buckets[bucket_index as usize];
+        bucket.swap_remove(index as usize);
+        if let Some(&(_, moved)) = bucket.get(index as usize) {
+            self.positions[moved as usize] = (bucket_index, index);
+        }
+        self.positions[value as usize] = NOT_IN_HEAP;
+    }
+}
+
+impl Default for RadixHeap {
+    fn default() -> Self {
+        RadixHeap::new()
+    }
+}
+
+impl MonotoneQueue for RadixHeap {
+    fn insert(&mut self, key: u32, value: u32) {
+        assert!(
+            key >= self.last_key,
+            "key {} is smaller than the last popped key {}",
+            key,
+            self.last_key
+        );
+        if self.positions.len() <= value as usize {
+            self.positions.resize(value as usize + 1, NOT_IN_HEAP);
+        }
+        match self.key(value) {
+            Some(old_key) if old_key <= key => return,
+            Some(_) => self.remove(value),
+            None => self.len += 1,
+        }
+        self.push(key, value);
+    }
+
+    fn pop(&mut self) -> Option<u32> {
+        if self.buckets[0].is_empty() {
+            let bucket_index = self.buckets.iter().position(|bucket| !bucket.is_empty())?;
+            let bucket = std::mem::take(&mut self.buckets[bucket_index]);
+            self.last_key = bucket.iter().map(|&(key, _)| key).min().unwrap();
+            for (key, value) in bucket {
+                self.push(key, value);
+            }
+        }
+
+        let (_, value) = self.buckets[0].pop()?;
+        self.positions[value as usize] = NOT_IN_HEAP;
+        self.len -= 1;
+        Some(value)
+    }
+
+    fn clear(&mut self) {
+        for bucket in self.buckets.iter_mut() {
+            for &(_, value) in bucket.iter() {
+                self.positions[value as usize] = NOT_IN_HEAP;
+            }
+            bucket.clear();
+        }
+        self.last_key = 0;
+        self.len = 0;
+    }
+}
+
+/// A binary heap is correct for arbitrary keys as well, but needs `O(log n)` per operation.
+impl MonotoneQueue for BinaryHeap<Reverse<(u32, u32)>> {
+    fn insert(&mut self, key: u32, value: u32) {
+        self.push(Reverse((key, value)));
+    }
+
+    fn pop(&mut self) -> Option<u32> {
+        BinaryHeap::pop(self).map(|Reverse((_, value))| value)
+    }
+
+    fn clear(&mut self) {
+        BinaryHeap::clear(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeSet, BinaryHeap, HashMap};
+
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use crate::{
+        geometry::Point,
+        routing::{Dijkstra, DijkstraWorkspace, Edge, Graph, MonotoneQueue, RadixHeap},
+    };
+
+    #[test]
+    fn radix_heap_pops_in_order() {
+        let mut rng = StdRng::seed_from_u64(6);
+        let mut radix_heap = RadixHeap::new();
+        // the entries by key and the key of each value, decreased like in the radix heap
+        let mut reference: BTreeSet<(u32, u32)> = BTreeSet::new();
+        let mut keys: HashMap<u32, u32> = HashMap::new();
+
+        let mut last_key = 0;
+        for _ in 0..10_000 {
+            if rng.gen_bool(0.6) {
+                let key = last_key + rng.gen_range(0..1_000_000);
+                let value = rng.gen_range(0..1_000);
+                if !keys.get(&value).is_some_and(|&old_key| old_key <= key) {
+                    if let Some(old_key) = keys.insert(value, key) {
+                        reference.remove(&(old_key, value));
+                    }
+                    reference.insert((key, value));
+                }
+                radix_heap.insert(key, value);
+                assert_eq!(radix_heap.key(value), keys.get(&value).copied());
+            } else if let Some(&(key, _)) = reference.first() {
+                // values with equal keys may be popped in any order, so compare by the key
+                let value = radix_heap.pop().unwrap();
+                assert_eq!(keys.remove(&value), Some(key));
+                assert!(reference.remove(&(key, value)));
+                last_key = key;
+            } else {
+                assert!(radix_heap.pop().is_none());
+            }
+            assert_eq!(radix_heap.len(), keys.len());
+        }
+    }
+
+    #[test]
+    fn radix_heap_decreases_keys() {
+        let mut radix_heap = RadixHeap::new();
+        radix_heap.insert(10, 1);
+        radix_heap.insert(20, 2);
+        radix_heap.insert(5, 2);
+        radix_heap.insert(30, 1);
+        assert_eq!(radix_heap.len(), 2);
+        assert_eq!(radix_heap.key(1), Some(10));
+        assert_eq!(radix_heap.pop(), Some(2));
+        assert_eq!(radix_heap.pop(), Some(1));
+        assert_eq!(radix_heap.pop(), None);
+        assert!(radix_heap.is_empty());
+    }
+
+    #[test]
+    fn radix_heap_dijkstra_matches_binary_heap() {
+        let mut rng = StdRng::seed_from_u64(6);
+        let num_nodes = 1_000;
+        let nodes = vec![Point::from_coordinate(0.0, 0.0); num_nodes as usize];
+        // costs far above the window of the bucket queue
+        let edges = (0..5_000)
+            .map(|_| Edge {
+                source_id: rng.gen_range(0..num_nodes),
+                target_id: rng.gen_range(0..num_nodes),
+                cost: rng.gen_range(0..10_000_000),
+            })
+            .collect();
+        let graph = Graph::new(nodes, edges);
+        let dijkstra = Dijkstra::new(&graph);
+
+        // the default workspace uses the radix heap
+        let mut radix_workspace = DijkstraWorkspace::new(graph.nodes.len());
+        let mut reference_workspace =
+            DijkstraWorkspace::with_queue(graph.nodes.len(), BinaryHeap::new());
+        for _ in 0..200 {
+            let from = rng.gen_range(0..num_nodes);
+            let to = rng.gen_range(0..num_nodes);
+            assert_eq!(
+                dijkstra.query(&mut radix_workspace, from, to),
+                dijkstra.query(&mut reference_workspace, from, to),
+                "wrong cost from {} to {}",
+                from,
+                to
+            );
+        }
+    }
 }
@@ -0,0 +1,395 @@
+use std::{collections::HashMap, ops::Range};
+
+use geojson::Value;
+
+use crate::{
+    geometry::{geojson_points, radians_to_meter, read_geojson_features, Arc, Point},
+    spatial_partition::PolygonSpatialPartition,
+    Error, Result,
+};
+
+use super::{Clearance, Edge, Graph, Route};
+
+/// The number of nearest nodes of the graph each end of a canal is connected to.
+const CANAL_CONNECTIONS: usize = 4;
+
+/// A canal or a narrow strait, e.g. Suez, Panama or Kiel, that the generated graph cannot connect
+/// because it crosses the coastlines. Its transit takes a fixed time, e.g. with the waiting for
+/// a convoy or the locks, and a toll.
+pub struct Canal {
+    pub name: String,
+    /// The course through the canal, the ends should lie in open water near the graph.
+    pub points: Vec<Point>,
+    pub transit_hours: f64,
+    pub toll: f64,
+    /// False if the canal is closed for all routes.
+    pub open: bool,
+    pub clearance: Clearance,
+}
+
+impl Canal {
+    /// Reads the canals of a GeoJSON file, the LineStrings of its features with a `name` and
+    /// `transit_hours` property. Optional properties are the `toll`, 0 if not given, `open`,
+    /// true if not given, and the `depth` and `height` limits in meters. Other geometries are
+    /// skipped.
+    pub fn from_geojson_file(path: &str) -> Result<Vec<Canal>> {
+        let mut canals = Vec::new();
+        for (i, feature) in read_geojson_features(path)?.into_iter().enumerate() {
+            let line = match feature.geometry.as_ref().map(|geometry| &geometry.value) {
+                Some(Value::LineString(line)) if line.len() >= 2 => line.clone(),
+                _ => continue,
+            };
+            let number = |key: &str| feature.property(key).and_then(|value| value.as_f64());
+            let name = feature
+                .property("name")
+                .and_then(|name| name.as_str())
+                .ok_or_else(|| Error::format(path, format!("feature {} has no name", i)))?;
+            let transit_hours = number("transit_hours")
+                .filter(|hours| hours.is_finite() && *hours >= 0.0)
+                .ok_or_else(|| {
+                    Error::format(path, format!("canal {} has no transit_hours", name))
+                })?;
+            let toll = number("toll").unwrap_or(0.0);
+            if !toll.is_finite() || toll < 0.0 {
+                return Err(Error::format(
+                    path,
+                    format!("canal {} has an invalid toll {}", name, toll),
+                ));
+            }
+            let points = geojson_points(path, &line)?;
+            canals.push(Canal {
+                name: name.to_string(),
+                points,
+                transit_hours,
+                toll,
+                open: feature
+                    .property("open")
+                    .and_then(|open| open.as_bool())
+                    .unwrap_or(true),
+                clearance: Clearance {
+                    depth: number("depth").map_or(f32::INFINITY, |depth| depth as f32),
+                    height: number("height").map_or(f32::INFINITY, |height| height as f32),
+                },
+            });
+            // the transit time of an edge is its share of the length
+            if canals.last().unwrap().length() <= 0.0 {
+                return Err(Error::format(path, format!("canal {} has no length", name)));
+            }
+        }
+
+        Ok(canals)
+    }
+
+    /// Returns the length of the course through the canal in meters.
+    pub fn length(&self) -> f64 {
+        self.points
+            .windows(2)
+            .map(|points| radians_to_meter(Arc::new(&points[0], &points[1]).central_angle()))
+            .sum()
+    }
+
+    /// Replaces the times of arrival in the canal along the points of a route, e.g. from
+    /// `SailingTime::etas`, with its transit time. The times after the canal are moved by the
+    /// difference. The canal is used between the points in `within`, e.g. of the leg that runs
+    /// through it, the points of those closest to the ends of the canal are taken as its entry
+    /// and exit. A route through the canal and back uses it once in each of two ranges.
+    pub fn apply_transit(&self, points: &[Point], etas: &mut [f64], within: Range<usize>) {
+        let closest = |end: &Point| {
+            within.clone().min_by(|&a, &b| {
+                let distance = |i: usize| Arc::new(&points[i], end).central_angle();
+                distance(a).total_cmp(&distance(b))
+            })
+        };
+        let (Some(first), Some(last)) = (
+            closest(&self.points[0]),
+            closest(self.points.last().unwrap()),
+        ) else {
+            return;
+        };
+        let (entry, exit) = (first.min(last), first.max(last));
+        let sailed = etas[exit] - etas[entry];
+        let transit = self.transit_hours * 3_600.0;
+        if sailed > 0.0 {
+            // the passage through the canal keeps its share of each part
+            for i in entry + 1..exit {
+                etas[i] = etas[entry] + (etas[i] - etas[entry]) * transit / sailed;
+            }
+        }
+        for eta in etas[exit..].iter_mut() {
+            *eta += transit - sailed;
+        }
+    }
+}
+
+/// The canals in a graph. Every point of a canal is a node, with edges both ways between them
+/// and between the ends and the nearest nodes of the graph.
+pub struct Canals {
+    canals: Vec<Canal>,
+    /// The canal of each edge through a canal, by the ids of its source and target. The ids of
+    /// the nodes stay the same if edges are removed, e.g. by `Graph::filter_edges`.
+    canal_edges: HashMap<(u32, u32), usize>,
+}
+
+impl Canals {
+    /// Returns the graph with the open canals added and the canals in it. If the planet grid is
+    /// given, the ends are only connected to the nearest nodes they reach without crossing land,
+    /// an end without any is an error.
+    pub fn add_to(
+        graph: &Graph,
+        canals: Vec<Canal>,
+        planet_grid: Option<&PolygonSpatialPartition>,
+    ) -> Result<(Graph, Canals)> {
+        let mut nodes = graph.nodes.clone();
+        let mut edges = graph.edges.to_vec();
+        let mut clearances = graph.clearances.to_vec();
+        let mut canal_edges = HashMap::new();
+        // adds the edges both ways between two nodes
+        let mut add_edges = |nodes: &[Point], source_id: u32, target_id: u32, clearance| {
+            let arc = Arc::new(&nodes[source_id as usize], &nodes[target_id as usize]);
+            let cost = radians_to_meter(arc.central_angle()) as u32;
+            for (source_id, target_id) in [(source_id, target_id), (target_id, source_id)] {
+                edges.push(Edge {
+                    source_id,
+                    target_id,
+                    cost,
+                });
+                clearances.push(clearance);
+            }
+        };
+
+        for (i, canal) in canals.iter().enumerate().filter(|(_, canal)| canal.open) {
+            let first_id = nodes.len() as u32;
+            nodes.extend(canal.points.iter().copied());
+            for source_id in first_id..nodes.len() as u32 - 1 {
+                add_edges(&nodes, source_id, source_id + 1, canal.clearance);
+                canal_edges.insert((source_id, source_id + 1), i);
+                canal_edges.insert((source_id + 1, source_id), i);
+            }
+            let ends = [first_id, nodes.len() as u32 - 1];
+            for end in ends {
+                let links: Vec<u32> = graph
+                    .k_nearest(&nodes[end as usize], CANAL_CONNECTIONS)
+                    .into_iter()
+                    .map(|(node_id, _)| node_id)
+                    .filter(|&node_id| {
+                        let arc = Arc::new(&nodes[end as usize], &nodes[node_id as usize]);
+                        !planet_grid.is_some_and(|planet_grid| planet_grid.check_collision(&arc))
+                    })
+                    .collect();
+                if links.is_empty() {
+                    return Err(Error::InvalidArgument(format!(
+                        "an end of canal {} has no link to the graph that does not cross land",
+                        canal.name
+                    )));
+                }
+                for node_id in links {
+                    add_edges(&nodes, end, node_id, Clearance::UNLIMITED);
+                }
+            }
+        }
+        println!(
+            "added {} canals with {} edges",
+            canals.iter().filter(|canal| canal.open).count(),
+            canal_edges.len()
+        );
+
+        let graph = Graph::with_clearances(nodes, edges, clearances);
+        Ok((
+            graph,
+            Canals {
+                canals,
+                canal_edges,
+            },
+        ))
+    }
+
+    pub fn canals(&self) -> &[Canal] {
+        &self.canals
+    }
+
+    /// Returns the index of the canal with the name, ignoring case.
+    pub fn index(&self, name: &str) -> Option<usize> {
+        self.canals
+            .iter()
+            .position(|canal| canal.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Returns the index of the canal the edge runs through, `None` for other edges.
+    pub fn canal_of(&self, edge: &Edge) -> Option<usize> {
+        self.canal_edges
+            .get(&(edge.source_id, edge.target_id))
+            .copied()
+    }
+
+    /// Returns the indices of the canals along the route, in order.
+    pub fn used_by(&self, route: &Route) -> Vec<usize> {
+        let mut used: Vec<usize> = Vec::new();
+        for canal in route.edges.iter().filter_map(|edge| self.canal_of(edge)) {
+            if used.last() != Some(&canal) {
+                used.push(canal);
+            }
+        }
+        used
+    }
+
+    /// Returns the seconds of the transit through the part of the canal the edge runs through,
+    /// `None` for other edges.
+    pub fn transit_seconds(&self, graph: &Graph, edge_id: u32) -> Option<u32> {
+        let canal = &self.canals[self.canal_of(&graph.edges[edge_id as usize])?];
+        let share = radians_to_meter(graph.edge_arc(edge_id).central_angle()) / canal.length();
+        Some((canal.transit_hours * 3_600.0 * share).round() as u32)
+    }
+
+    /// Returns the cost of the edge for `Dijkstra::query_with_weights`, which blocks the edges
+    /// through the closed canals, given by their indices, and keeps the cost of others.
+    pub fn weight(&self, edge: &Edge, cost: u32, closed: &[usize]) -> Option<u32> {
+        match self.canal_of(edge) {
+            Some(canal) if closed.contains(&canal) => None,
+            _ => Some(cost),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        geometry::{Point, Polygon},
+        routing::{random_graph, Canal, Canals, Clearance, Dijkstra, DijkstraWorkspace, Graph},
+        spatial_partition::PolygonSpatialPartition,
+        TestFile,
+    };
+
+    fn canal() -> Canal {
+        Canal {
+            name: "Test Canal".to_string(),
+            points: vec![
+                Point::from_coordinate(10.0, 20.0),
+                Point::from_coordinate(10.1, 20.1),
+                Point::from_coordinate(10.2, 20.2),
+            ],
+            transit_hours: 12.0,
+            toll: 1_000.0,
+            open: true,
+            clearance: Clearance::UNLIMITED,
+        }
+    }
+
+    #[test]
+    fn routes_use_open_canals() {
+        // a graph without edges, so the only way between the ends is the canal
+        let graph = random_graph(1_000, 0);
+        let (with_canal, canals) = Canals::add_to(&graph, vec![canal()], None).unwrap();
+        assert_eq!(canals.index("test canal"), Some(0));
+        let dijkstra = Dijkstra::new(&with_canal);
+        let mut workspace = DijkstraWorkspace::new(with_canal.nodes.len());
+
+        let from = graph.k_nearest(&canal().points[0], 1)[0].0;
+        let to = graph.k_nearest(&canal().points[2], 1)[0].0;
+        dijkstra.query(&mut workspace, from, to);
+        let route = workspace.route(&with_canal, from, to).unwrap();
+        assert_eq!(canals.used_by(&route), vec![0]);
+
+        let closed = dijkstra.query_with_weights(&mut workspace, from, to, |_, edge| {
+            canals.weight(edge, edge.cost, &[0])
+        });
+        assert_eq!(closed, u32::MAX);
+        let closed_canal = Canal {
+            open: false,
+            ..canal()
+        };
+        let (without_canal, _) = Canals::add_to(&graph, vec![closed_canal], None).unwrap();
+        assert_eq!(without_canal.edges.len(), graph.edges.len());
+    }
+
+    #[test]
+    fn end_links_keep_clear_of_land() {
+        let point = |lat, lon| Point::from_coordinate(lat, lon);
+        // a wall of land west of the first end of the canal
+        let wall = Polygon::new(vec![
+            point(9.9, 19.96),
+            point(9.9, 19.98),
+            point(10.1, 19.98),
+            point(10.1, 19.96),
+            point(9.9, 19.96),
+        ]);
+        let mut planet_grid = PolygonSpatialPartition::new(50);
+        planet_grid.add_polygons(&vec![wall]);
+        let west = vec![point(10.0, 19.9), point(10.01, 19.9)];
+        let graph = Graph::new(west.clone(), Vec::new());
+        assert!(Canals::add_to(&graph, vec![canal()], Some(&planet_grid)).is_err());
+
+        let mut nodes = west;
+        nodes.push(point(10.0, 20.05));
+        let graph = Graph::new(nodes, Vec::new());
+        let (with_canal, _) = Canals::add_to(&graph, vec![canal()], Some(&planet_grid)).unwrap();
+        let first_end = graph.nodes.len() as u32;
+        let links: Vec<u32> = with_canal
+            .outgoing_edge_ids(first_end)
+            .map(|edge_id| with_canal.edges[edge_id as usize].target_id)
+            .filter(|&node_id| node_id < first_end)
+            .collect();
+        assert_eq!(links, vec![2]);
+    }
+
+    #[test]
+    fn transit_replaces_the_sailed_time() {
+        let canal = canal();
+        let points = vec![
+            Point::from_coordinate(9.9, 20.0),
+            canal.points[0],
+            canal.points[1],
+            canal.points[2],
+            Point::from_coordinate(10.3, 20.2),
+        ];
+        let mut etas = vec![0.0, 1_000.0, 2_000.0, 4_000.0, 5_000.0];
+        canal.apply_transit(&points, &mut etas, 0..points.len());
+        assert_eq!(etas, vec![0.0, 1_000.0, 15_400.0, 44_200.0, 45_200.0]);
+    }
+
+    #[test]
+    fn transit_there_and_back() {
+        let canal = canal();
+        let points = vec![
+            Point::from_coordinate(9.9, 20.0),
+            canal.points[0],
+            canal.points[1],
+            canal.points[2],
+            Point::from_coordinate(10.3, 20.2),
+            canal.points[2],
+            canal.points[1],
+            canal.points[0],
+            Point::from_coordinate(9.9, 20.0),
+        ];
+        let mut etas: Vec<f64> = (0..9).map(|i| i as f64 * 1_000.0).collect();
+        // the legs out and back meet at the point beyond the canal
+        canal.apply_transit(&points, &mut etas, 0..5);
+        canal.apply_transit(&points, &mut etas, 4..9);
+        let transit = 12.0 * 3_600.0;
+        assert_eq!(etas[3] - etas[1], transit);
+        assert_eq!(etas[7] - etas[5], transit);
+        assert_eq!(etas[8], 8_000.0 + 2.0 * (transit - 2_000.0));
+    }
+
+    #[test]
+    fn invalid_canals_are_format_errors() {
+        let read = |coordinates: &str, properties: &str| {
+            let file = TestFile::new(
+                "canals.geojson",
+                format!(
+                    "{{\"type\":\"FeatureCollection\",\"features\":[{{\"type\":\"Feature\",\
+                     \"properties\":{{\"name\":\"Test Canal\",{}}},\
+                     \"geometry\":{{\"type\":\"LineString\",\"coordinates\":{}}}}}]}}",
+                    properties, coordinates
+                ),
+            );
+            Canal::from_geojson_file(file.path())
+        };
+        let line = "[[20.0,10.0],[20.1,10.1]]";
+
+        let canals = read(line, r#""transit_hours":12,"toll":1000"#).unwrap();
+        assert_eq!(canals[0].toll, 1_000.0);
+        assert!(read("[[20.0,10.0],[20.0,10.0]]", r#""transit_hours":12"#).is_err());
+        assert!(read(line, r#""transit_hours":-1"#).is_err());
+        assert!(read(line, r#""transit_hours":12,"toll":-1"#).is_err());
+    }
+}
@@ -0,0 +1,507 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use indicatif::ProgressBar;
+
+use super::Graph;
+
+/// Number of nodes a witness search may settle before it gives up and a shortcut is added.
+const WITNESS_SEARCH_LIMIT: usize = 500;
+
+/// Describes what an edge of the hierarchy stands for.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ChEdgeKind {
+    /// An edge of the original graph, given by its index in `Graph::edges`.
+    Original(u32),
+    /// A shortcut that replaces two consecutive edges of the hierarchy, given by their indices in
+    /// `ContractionHierarchy::edges`.
+    Shortcut(u32, u32),
+}
+
+#[derive(Clone)]
+pub struct ChEdge {
+    pub source_id: u32,
+    pub target_id: u32,
+    pub cost: u32,
+    pub kind: ChEdgeKind,
+}
+
+/// A contraction hierarchy over a `Graph`. Nodes are contracted one after another, and whenever a
+/// contracted node lies on the only shortest path between two of its neighbours, a shortcut edge
+/// is inserted. Queries then only need to follow edges that lead to higher levels.
+pub struct ContractionHierarchy {
+    pub levels: Vec<u32>,
+    pub edges: Vec<ChEdge>,
+    /// Edges leading from a node to a node of a higher level, grouped by source.
+    pub up_edges: Vec<u32>,
+    pub up_edges_start_at: Vec<u32>,
+    /// Edges leading from a node of a higher level to a node, grouped by target.
+    pub down_edges: Vec<u32>,
+    pub down_edges_start_at: Vec<u32>,
+}
+
+/// Local Dijkstra used during contraction to check if a shortcut is necessary.
+struct WitnessSearch {
+    node_cost: Vec<u32>,
+    touched: Vec<u32>,
+    queue: BinaryHeap<Reverse<(u32, u32)>>,
+}
+
+/// The graph that is left over during contraction.
+struct Remaining {
+    edges: Vec<ChEdge>,
+    outgoing: Vec<Vec<u32>>,
+    incoming: Vec<Vec<u32>>,
+    is_contracted: Vec<bool>,
+}
+
+impl ContractionHierarchy {
+    /// Contracts all nodes of the graph, using the edge difference plus the number of already
+    /// contracted neighbours as priority.
+    pub fn new(graph: &Graph) -> ContractionHierarchy {
+        let num_nodes = graph.nodes.len();
+        let mut remaining = Remaining::new(graph);
+        let mut witness_search = WitnessSearch::new(num_nodes);
+
+        let mut contracted_neighbours = vec![0; num_nodes];
+        let mut queue: BinaryHeap<Reverse<(i32, u32)>> = (0..num_nodes as u32)
+            .map(|node_id| {
+                let priority = remaining.priority(&mut witness_search, node_id, 0);
+                Reverse((priority, node_id))
+            })
+            .collect();
+
+        println!("contracting {} nodes", num_nodes);
+        let pb = ProgressBar::new(num_nodes as u64);
+        let mut levels = vec![0; num_nodes];
+        let mut level = 0;
+        while let Some(Reverse((_, node_id))) = queue.pop() {
+            if remaining.is_contracted[node_id as usize] {
+                continue;
+            }
+
+            // lazy update, contract only if the node is still the most promising one
+            let priority = remaining.priority(
+                &mut witness_search,
+                node_id,
+                contracted_neighbours[node_id as usize],
+            );
+            if let Some(Reverse((next_priority, _))) = queue.peek() {
+                if priority > *next_priority {
+                    queue.push(Reverse((priority, node_id)));
+                    continue;
+                }
+            }
+
+            for neighbour in remaining.neighbours(node_id) {
+                contracted_neighbours[neighbour as usize] += 1;
+            }
+            for shortcut in remaining.shortcuts(&mut witness_search, node_id) {
+                remaining.add_edge(shortcut);
+            }
+            remaining.is_contracted[node_id as usize] = true;
+            levels[node_id as usize] = level;
+            level += 1;
+            pb.inc(1);
+        }
+        pb.finish();
+
+        let edges = remaining.edges;
+        let (up_edges, up_edges_start_at) = group_edges(&edges, num_nodes, |edge| {
+            (levels[edge.source_id as usize] < levels[edge.target_id as usize])
+                .then_some(edge.source_id)
+        });
+        let (down_edges, down_edges_start_at) = group_edges(&edges, num_nodes, |edge| {
+            (levels[edge.source_id as usize] > levels[edge.target_id as usize])
+                .then_some(edge.target_id)
+        });
+
+        ContractionHierarchy {
+            levels,
+            edges,
+            up_edges,
+            up_edges_start_at,
+            down_edges,
+            down_edges_start_at,
+        }
+    }
+
+    /// Searches upwards from both ends and returns the ids of the original edges of the path in
+    /// order, with shortcuts unpacked, and its cost, e.g. for `get_route_of_edges`. No edges and
+    /// `u32::MAX` if the target is not reachable.
+    pub fn query(&self, from_node_id: u32, to_node_id: u32) -> (Vec<u32>, u32) {
+        let num_nodes = self.levels.len();
+        let mut forward = UpwardSearch::new(num_nodes, from_node_id);
+        let mut backward = UpwardSearch::new(num_nodes, to_node_id);
+
+        let mut best: Option<(u32, u32)> = None; // cost, meeting node
+        loop {
+            let best_cost = best.map_or(u32::MAX, |(cost, _)| cost);
+            let forward_done = forward.min_key().is_none_or(|key| key >= best_cost);
+            let backward_done = backward.min_key().is_none_or(|key| key >= best_cost);
+            if forward_done && backward_done {
+                break;
+            }
+
+            let (search, other, edges, edges_start_at, is_forward) =
+                if !forward_done && (backward_done || forward.min_key() <= backward.min_key()) {
+                    (
+                        &mut forward,
+                        &backward,
+                        &self.up_edges,
+                        &self.up_edges_start_at,
+                        true,
+                    )
+                } else {
+                    (
+                        &mut backward,
+                        &forward,
+                        &self.down_edges,
+                        &self.down_edges_start_at,
+                        false,
+                    )
+                };
+
+            let Some(node_id) = search.pop() else {
+                continue;
+            };
+            let cost = search.node_cost[node_id as usize];
+            if other.node_cost[node_id as usize] != u32::MAX {
+                let total_cost = cost + other.node_cost[node_id as usize];
+                if total_cost < best_cost {
+                    best = Some((total_cost, node_id));
+                }
+            }
+
+            for &edge_id in &edges[edges_start_at[node_id as usize] as usize
+                ..edges_start_at[node_id as usize + 1] as usize]
+            {
+                let edge = &self.edges[edge_id as usize];
+                let next_id = if is_forward {
+                    edge.target_id
+                } else {
+                    edge.source_id
+                };
+                search.relax(next_id, cost + edge.cost, edge_id);
+            }
+        }
+
+        let Some((cost, meeting_node_id)) = best else {
+            return (Vec::new(), u32::MAX);
+        };
+
+        // collect the hierarchy edges from source to meeting node and from there to target
+        let mut path = Vec::new();
+        let mut current = meeting_node_id;
+        while let Some(edge_id) = forward.edge_from_predecessor[current as usize] {
+            path.push(edge_id);
+            current = self.edges[edge_id as usize].source_id;
+        }
+        path.reverse();
+        let mut current = meeting_node_id;
+        while let Some(edge_id) = backward.edge_from_predecessor[current as usize] {
+            path.push(edge_id);
+            current = self.edges[edge_id as usize].target_id;
+        }
+
+        let mut edge_ids = Vec::new();
+        for edge_id in path {
+            self.unpack(edge_id, &mut |edge| match edge.kind {
+                ChEdgeKind::Original(original_id) => edge_ids.push(original_id),
+                ChEdgeKind::Shortcut(_, _) => unreachable!(),
+            });
+        }
+
+        (edge_ids, cost)
+    }
+
+    /// Calls `f` for every original edge that the hierarchy edge stands for, in path order.
+    pub fn unpack(&self, edge_id: u32, f: &mut impl FnMut(&ChEdge)) {
+        let mut stack = vec![edge_id];
+        while let Some(edge_id) = stack.pop() {
+            let edge = &self.edges[edge_id as usize];
+            match edge.kind {
+                ChEdgeKind::Original(_) => f(edge),
+                ChEdgeKind::Shortcut(first, second) => {
+                    stack.push(second);
+                    stack.push(first);
+                }
+            }
+        }
+    }
+}
+
+/// One direction of a query, which only relaxes edges leading to higher levels.
+struct UpwardSearch {
+    node_cost: Vec<u32>,
+    edge_from_predecessor: Vec<Option<u32>>,
+    queue: BinaryHeap<Reverse<(u32, u32)>>,
+}
+
+impl UpwardSearch {
+    fn new(num_nodes: usize, start_node_id: u32) -> UpwardSearch {
+        let mut node_cost = vec![u32::MAX; num_nodes];
+        node_cost[start_node_id as usize] = 0;
+        UpwardSearch {
+            node_cost,
+            edge_from_predecessor: vec![None; num_nodes],
+            queue: BinaryHeap::from(vec![Reverse((0, start_node_id))]),
+        }
+    }
+
+    /// Returns the smallest key in the queue, skipping entries that are outdated.
+    fn min_key(&mut self) -> Option<u32> {
+        while let Some(&Reverse((cost, node_id))) = self.queue.peek() {
+            if cost == self.node_cost[node_id as usize] {
+                return Some(cost);
+            }
+            self.queue.pop();
+        }
+        None
+    }
+
+    fn pop(&mut self) -> Option<u32> {
+        self.min_key()?;
+        self.queue.pop().map(|Reverse((_, node_id))| node_id)
+    }
+
+    fn relax(&mut self, node_id: u32, cost: u32, edge_id: u32) {
+        if cost < self.node_cost[node_id as usize] {
+            self.node_cost[node_id as usize] = cost;
+            self.edge_from_predecessor[node_id as usize] = Some(edge_id);
+            self.queue.push(Reverse((cost, node_id)));
+        }
+    }
+}
+
+impl Remaining {
+    fn new(graph: &Graph) -> Remaining {
+        let num_nodes = graph.nodes.len();
+        let mut remaining = Remaining {
+            edges: Vec::with_capacity(graph.edges.len()),
+            outgoing: vec![Vec::new(); num_nodes],
+            incoming: vec![Vec::new(); num_nodes],
+            is_contracted: vec![false; num_nodes],
+        };
+        graph
+            .edges
+            .iter()
+            .enumerate()
+            .filter(|(_, edge)| edge.source_id != edge.target_id)
+            .for_each(|(edge_id, edge)| {
+                remaining.add_edge(ChEdge {
+                    source_id: edge.source_id,
+                    target_id: edge.target_id,
+                    cost: edge.cost,
+                    kind: ChEdgeKind::Original(edge_id as u32),
+                })
+            });
+        remaining
+    }
+
+    fn add_edge(&mut self, edge: ChEdge) {
+        let edge_id = self.edges.len() as u32;
+        self.outgoing[edge.source_id as usize].push(edge_id);
+        self.incoming[edge.target_id as usize].push(edge_id);
+        self.edges.push(edge);
+    }
+
+    fn neighbours(&self, node_id: u32) -> Vec<u32> {
+        let mut neighbours: Vec<u32> = self.incoming[node_id as usize]
+            .iter()
+            .map(|&edge_id| self.edges[edge_id as usize].source_id)
+            .chain(
+                self.outgoing[node_id as usize]
+                    .iter()
+                    .map(|&edge_id| self.edges[edge_id as usize].target_id),
+            )
+            .filter(|&neighbour| !self.is_contracted[neighbour as usize])
+            .collect();
+        neighbours.sort_unstable();
+        neighbours.dedup();
+        neighbours
+    }
+
+    fn priority(
+        &self,
+        witness_search: &mut WitnessSearch,
+        node_id: u32,
+        contracted_neighbours: i32,
+    ) -> i32 {
+        let num_shortcuts = self.shortcuts(witness_search, node_id).len() as i32;
+        let num_removed = self.incoming[node_id as usize]
+            .iter()
+            .chain(self.outgoing[node_id as usize].iter())
+            .filter(|&&edge_id| {
+                let edge = &self.edges[edge_id as usize];
+                !self.is_contracted[edge.source_id as usize]
+                    && !self.is_contracted[edge.target_id as usize]
+            })
+            .count() as i32;
+        num_shortcuts - num_removed + contracted_neighbours
+    }
+
+    /// Returns the shortcuts that are needed if `node_id` is contracted.
+    fn shortcuts(&self, witness_search: &mut WitnessSearch, node_id: u32) -> Vec<ChEdge> {
+        let outgoing: Vec<u32> = self.outgoing[node_id as usize]
+            .iter()
+            .copied()
+            .filter(|&edge_id| !self.is_contracted[self.edges[edge_id as usize].target_id as usize])
+            .collect();
+        let max_outgoing_cost = outgoing
+            .iter()
+            .map(|&edge_id| self.edges[edge_id as usize].cost)
+            .max()
+            .unwrap_or(0);
+
+        let mut shortcuts = Vec::new();
+        for &incoming_id in self.incoming[node_id as usize].iter() {
+            let incoming = &self.edges[incoming_id as usize];
+            if self.is_contracted[incoming.source_id as usize] {
+                continue;
+            }
+
+            witness_search.run(
+                self,
+                incoming.source_id,
+                node_id,
+                incoming.cost + max_outgoing_cost,
+            );
+            for &outgoing_id in outgoing.iter() {
+                let outgoing = &self.edges[outgoing_id as usize];
+                if outgoing.target_id == incoming.source_id {
+                    continue;
+                }
+                let cost = incoming.cost + outgoing.cost;
+                if witness_search.cost(outgoing.target_id) > cost {
+                    shortcuts.push(ChEdge {
+                        source_id: incoming.source_id,
+                        target_id: outgoing.target_id,
+                        cost,
+                        kind: ChEdgeKind::Shortcut(incoming_id, outgoing_id),
+                    });
+                }
+            }
+        }
+
+        // keep only the cheapest shortcut between two nodes
+        shortcuts.sort_unstable_by_key(|shortcut| {
+            (shortcut.source_id, shortcut.target_id, shortcut.cost)
+        });
+        shortcuts.dedup_by_key(|shortcut| (shortcut.source_id, shortcut.target_id));
+        shortcuts
+    }
+}
+
+impl WitnessSearch {
+    fn new(num_nodes: usize) -> WitnessSearch {
+        WitnessSearch {
+            node_cost: vec![u32::MAX; num_nodes],
+            touched: Vec::new(),
+            queue: BinaryHeap::new(),
+        }
+    }
+
+    fn cost(&self, node_id: u32) -> u32 {
+        self.node_cost[node_id as usize]
+    }
+
+    /// Runs a Dijkstra from `from_node_id` on the remaining graph without `ignore_node_id`, that
+    /// stops once `max_cost` is exceeded or `WITNESS_SEARCH_LIMIT` nodes are settled.
+    fn run(
+        &mut self,
+        remaining: &Remaining,
+        from_node_id: u32,
+        ignore_node_id: u32,
+        max_cost: u32,
+    ) {
+        for node_id in self.touched.drain(..) {
+            self.node_cost[node_id as usize] = u32::MAX;
+        }
+        self.queue.clear();
+
+        self.node_cost[from_node_id as usize] = 0;
+        self.touched.push(from_node_id);
+        self.queue.push(Reverse((0, from_node_id)));
+
+        let mut settled = 0;
+        while let Some(Reverse((cost, node_id))) = self.queue.pop() {
+            if cost > self.node_cost[node_id as usize] {
+                continue;
+            }
+            if cost > max_cost || settled >= WITNESS_SEARCH_LIMIT {
+                break;
+            }
+            settled += 1;
+
+            for &edge_id in remaining.outgoing[node_id as usize].iter() {
+                let edge = &remaining.edges[edge_id as usize];
+                if edge.target_id == ignore_node_id
+                    || remaining.is_contracted[edge.target_id as usize]
+                {
+                    continue;
+                }
+                let alternative_cost = cost + edge.cost;
+                if alternative_cost < self.node_cost[edge.target_id as usize] {
+                    if self.node_cost[edge.target_id as usize] == u32::MAX {
+                        self.touched.push(edge.target_id);
+                    }
+                    self.node_cost[edge.target_id as usize] = alternative_cost;
+                    self.queue.push(Reverse((alternative_cost, edge.target_id)));
+                }
+            }
+        }
+    }
+}
+
+/// Groups the ids of all edges for which `key` returns a node id by that node id, returning the
+/// edge ids and the offset array.
+fn group_edges(
+    edges: &[ChEdge],
+    num_nodes: usize,
+    key: impl Fn(&ChEdge) -> Option<u32>,
+) -> (Vec<u32>, Vec<u32>) {
+    let mut keyed_edges: Vec<(u32, u32)> = edges
+        .iter()
+        .enumerate()
+        .filter_map(|(edge_id, edge)| Some((key(edge)?, edge_id as u32)))
+        .collect();
+    keyed_edges.sort_unstable();
+
+    let mut edges_start_at = vec![0; num_nodes + 1];
+    keyed_edges
+        .iter()
+        .for_each(|&(node_id, _)| edges_start_at[node_id as usize + 1] += 1);
+    for node_id in 0..num_nodes {
+        edges_start_at[node_id + 1] += edges_start_at[node_id];
+    }
+
+    (
+        keyed_edges
+            .into_iter()
+            .map(|(_, edge_id)| edge_id)
+            .collect(),
+        edges_start_at,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::routing::{
+        assert_same_cost_as_dijkstra, edge_case_graph, get_route_of_edges, random_graph,
+        ContractionHierarchy,
+    };
+
+    #[test]
+    fn same_cost_as_dijkstra() {
+        for graph in [random_graph(1_000, 3_000), edge_case_graph()] {
+            let ch = ContractionHierarchy::new(&graph);
+            // the used edges are unpacked from the shortcuts, so the route has the found cost
+            assert_same_cost_as_dijkstra(&graph, |from, to| {
+                let (edge_ids, cost) = ch.query(from, to);
+                let route =
+                    (cost != u32::MAX).then(|| get_route_of_edges(&graph, from, to, &edge_ids));
+                (cost, route)
+            });
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use super::{get_route_by, Graph, MonotoneQueue, RadixHeap, Route};
+
+/// The state of a `Dijkstra` query. Allocating it once and reusing it for many queries avoids
+/// allocating vectors of the size of the graph for every query. Only the nodes touched by the
+/// last query are reset. The priority queue can be chosen, by default the `RadixHeap` is used,
+/// which is correct for any costs.
+pub struct DijkstraWorkspace<Q: MonotoneQueue = RadixHeap> {
+    pub(super) queue: Q,
+    pub(super) node_cost: Vec<u32>,
+    pub(super) edge_from_predecessor: Vec<Option<u32>>,
+    pub(super) is_expanded: Vec<bool>,
+    touched: Vec<u32>,
+}
+
+impl DijkstraWorkspace {
+    pub fn new(num_nodes: usize) -> DijkstraWorkspace {
+        DijkstraWorkspace::with_queue(num_nodes, RadixHeap::new())
+    }
+}
+
+impl<Q: MonotoneQueue> DijkstraWorkspace<Q> {
+    pub fn with_queue(num_nodes: usize, queue: Q) -> DijkstraWorkspace<Q> {
+        DijkstraWorkspace {
+            queue,
+            node_cost: vec![u32::MAX; num_nodes],
+            edge_from_predecessor: vec![None; num_nodes],
+            is_expanded: vec![false; num_nodes],
+            touched: Vec::new(),
+        }
+    }
+
+    /// Returns the cost of the node found by the last query, or `u32::MAX` if it was not reached.
+    pub fn cost(&self, node_id: u32) -> u32 {
+        self.node_cost[node_id as usize]
+    }
+
+    /// Returns the id of the edge leading to the node in the last query.
+    pub fn edge_from_predecessor(&self, node_id: u32) -> Option<u32> {
+        self.edge_from_predecessor[node_id as usize]
+    }
+
+    /// Returns the route from `start` to `end` found by the last query.
+    pub fn route(&self, graph: &Graph, start: u32, end: u32) -> Option<Route> {
+        get_route_by(graph, start, end, |node_id| {
+            self.edge_from_predecessor(node_id)
+        })
+    }
+
+    pub(super) fn clear(&mut self) {
+        for node_id in self.touched.drain(..) {
+            self.node_cost[node_id as usize] = u32::MAX;
+            self.edge_from_predecessor[node_id as usize] = None;
+            self.is_expanded[node_id as usize] = false;
+        }
+        self.queue.clear();
+    }
+
+    /// Lowers the cost of the node if `cost` is smaller than its current cost.
+    pub(super) fn relax(&mut self, node_id: u32, cost: u32, edge_id: Option<u32>) {
+        if cost < self.node_cost[node_id as usize] {
+            if self.node_cost[node_id as usize] == u32::MAX {
+                self.touched.push(node_id);
+            }
+            self.node_cost[node_id as usize] = cost;
+            self.edge_from_predecessor[node_id as usize] = edge_id;
+            self.queue.insert(cost, node_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::routing::{
+        assert_same_cost_as_dijkstra, edge_case_graph, random_graph, Dijkstra, DijkstraWorkspace,
+    };
+
+    #[test]
+    fn reused_workspace_gives_same_routes() {
+        for graph in [random_graph(1_000, 3_000), edge_case_graph()] {
+            let dijkstra = Dijkstra::new(&graph);
+            let mut workspace = DijkstraWorkspace::new(graph.nodes.len());
+            assert_same_cost_as_dijkstra(&graph, |from, to| {
+                let cost = dijkstra.query(&mut workspace, from, to);
+                let (used_edges, _) = dijkstra.dijkstra(from, to);
+                for node_id in 0..graph.nodes.len() as u32 {
+                    assert_eq!(
+                        workspace.edge_from_predecessor(node_id),
+                        used_edges[node_id as usize]
+                    );
+                }
+                (cost, workspace.route(&graph, from, to))
+            });
+        }
+    }
+}
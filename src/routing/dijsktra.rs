@@ -1,4 +1,4 @@
-use super::{BucketQueue, Graph};
+use super::{DijkstraWorkspace, Edge, Graph, MonotoneQueue, RadixHeap, Route};
 
 #[derive(Clone)]
 pub struct Dijkstra<'a> {
@@ -11,46 +11,296 @@ impl<'a> Dijkstra<'a> {
     }
 
     pub fn dijkstra(&self, from_node_id: u32, to_node_id: u32) -> (Vec<Option<u32>>, u32) {
-        let mut queue = BucketQueue::new(60_000);
+        let mut workspace = DijkstraWorkspace::new(self.graph.nodes.len());
+        let cost = self.query(&mut workspace, from_node_id, to_node_id);
+        (workspace.edge_from_predecessor, cost)
+    }
+
+    /// Same as `dijkstra`, but keeps the search state in `workspace` instead of allocating it. The
+    /// workspace is reset at the start of the query, only touching the nodes of the last search,
+    /// so the cost of a query scales with its search space. Returns the cost, which is `u32::MAX`
+    /// if `to_node_id` is not reachable. The route can be read with `DijkstraWorkspace::route`.
+    pub fn query<Q: MonotoneQueue>(
+        &self,
+        workspace: &mut DijkstraWorkspace<Q>,
+        from_node_id: u32,
+        to_node_id: u32,
+    ) -> u32 {
+        self.query_with_weights(workspace, from_node_id, to_node_id, |_, edge| {
+            Some(edge.cost)
+        })
+    }
+
+    /// Same as `query`, but the cost of an edge is given by `weight`, which is called with the id
+    /// of the edge and the edge itself. Returning `None` blocks the edge for this query. The
+    /// returned cost is the summed weight, while `DijkstraWorkspace::route` still sums up the
+    /// original edge costs.
+    pub fn query_with_weights<Q: MonotoneQueue>(
+        &self,
+        workspace: &mut DijkstraWorkspace<Q>,
+        from_node_id: u32,
+        to_node_id: u32,
+        weight: impl Fn(u32, &Edge) -> Option<u32>,
+    ) -> u32 {
+        workspace.clear();
+        workspace.relax(from_node_id, 0, None);
+
+        while let Some(node_id) = workspace.queue.pop() {
+            if workspace.is_expanded[node_id as usize] {
+                continue;
+            }
+            if node_id == to_node_id {
+                break;
+            }
+            workspace.is_expanded[node_id as usize] = true;
+
+            let cost = workspace.node_cost[node_id as usize];
+            for edge_id in self.graph.outgoing_edge_ids(node_id) {
+                let edge = &self.graph.edges[edge_id as usize];
+                if let Some(weight) = weight(edge_id, edge) {
+                    workspace.relax(edge.target_id, cost.saturating_add(weight), Some(edge_id));
+                }
+            }
+        }
+
+        workspace.cost(to_node_id)
+    }
+
+    /// Routes along the waypoints in the given order, one query per leg. Returns the route of each
+    /// leg, or `None` if any waypoint can not be reached from the one before.
+    pub fn route_via<Q: MonotoneQueue>(
+        &self,
+        workspace: &mut DijkstraWorkspace<Q>,
+        waypoints: &[u32],
+    ) -> Option<Vec<Route>> {
+        waypoints
+            .windows(2)
+            .map(|leg| {
+                self.query(workspace, leg[0], leg[1]);
+                workspace.route(self.graph, leg[0], leg[1])
+            })
+            .collect()
+    }
+
+    /// Finds the cheapest route from any of the sources to any of the targets, each given as a node
+    /// id and the cost to reach the node or the target from it, e.g. the cost of the connection to
+    /// a position between nodes. The returned route starts at the chosen source and ends at the
+    /// chosen target node, its cost does not include the given costs.
+    pub fn route_between<Q: MonotoneQueue>(
+        &self,
+        workspace: &mut DijkstraWorkspace<Q>,
+        sources: &[(u32, u32)],
+        targets: &[(u32, u32)],
+    ) -> Option<Route> {
+        self.route_between_with_weights(workspace, sources, targets, |_, edge| Some(edge.cost))
+    }
+
+    /// Same as `route_between`, but the cost of an edge is given by `weight`, like in
+    /// `query_with_weights`. The cost of the returned route is still the sum of the edge costs.
+    pub fn route_between_with_weights<Q: MonotoneQueue>(
+        &self,
+        workspace: &mut DijkstraWorkspace<Q>,
+        sources: &[(u32, u32)],
+        targets: &[(u32, u32)],
+        weight: impl Fn(u32, &Edge) -> Option<u32>,
+    ) -> Option<Route> {
+        self.route_between_time_dependent(workspace, sources, targets, |edge_id, edge, _| {
+            weight(edge_id, edge)
+        })
+    }
+
+    /// Same as `route_between_with_weights`, but `weight` is also called with the cost at the
+    /// source of the edge, e.g. the seconds since departure for the time to sail an edge with the
+    /// currents at that time. Weights have to be FIFO: entering an edge later never leaves it
+    /// earlier, otherwise the route may not be the cheapest.
+    pub fn route_between_time_dependent<Q: MonotoneQueue>(
+        &self,
+        workspace: &mut DijkstraWorkspace<Q>,
+        sources: &[(u32, u32)],
+        targets: &[(u32, u32)],
+        weight: impl Fn(u32, &Edge, u32) -> Option<u32>,
+    ) -> Option<Route> {
+        workspace.clear();
+        for &(node_id, cost) in sources {
+            workspace.relax(node_id, cost, None);
+        }
+
+        // the total cost and the node of the best target found so far
+        let mut best: Option<(u32, u32)> = None;
+        while let Some(node_id) = workspace.queue.pop() {
+            if workspace.is_expanded[node_id as usize] {
+                continue;
+            }
+            let cost = workspace.node_cost[node_id as usize];
+            if best.is_some_and(|(best_cost, _)| cost >= best_cost) {
+                break;
+            }
+            workspace.is_expanded[node_id as usize] = true;
+
+            for &(target_id, target_cost) in targets {
+                let total_cost = cost.saturating_add(target_cost);
+                if target_id == node_id && best.is_none_or(|(best_cost, _)| total_cost < best_cost)
+                {
+                    best = Some((total_cost, node_id));
+                }
+            }
+
+            for edge_id in self.graph.outgoing_edge_ids(node_id) {
+                let edge = &self.graph.edges[edge_id as usize];
+                if let Some(weight) = weight(edge_id, edge, cost) {
+                    workspace.relax(edge.target_id, cost.saturating_add(weight), Some(edge_id));
+                }
+            }
+        }
+
+        let (_, end) = best?;
+        let mut start = end;
+        while let Some(edge_id) = workspace.edge_from_predecessor(start) {
+            start = self.graph.edges[edge_id as usize].source_id;
+        }
+        workspace.route(self.graph, start, end)
+    }
+
+    /// Returns the cost from `from_node_id` to each of the targets, or `u32::MAX` if a target is
+    /// not reachable. The search stops as soon as all targets are expanded.
+    pub fn one_to_many<Q: MonotoneQueue>(
+        &self,
+        workspace: &mut DijkstraWorkspace<Q>,
+        from_node_id: u32,
+        to_node_ids: &[u32],
+    ) -> Vec<u32> {
+        let mut remaining_targets = to_node_ids.to_vec();
+        remaining_targets.sort_unstable();
+        remaining_targets.dedup();
+        if remaining_targets.is_empty() {
+            return Vec::new();
+        }
+
+        workspace.clear();
+        workspace.relax(from_node_id, 0, None);
+
+        while let Some(node_id) = workspace.queue.pop() {
+            if workspace.is_expanded[node_id as usize] {
+                continue;
+            }
+            workspace.is_expanded[node_id as usize] = true;
+            if let Ok(index) = remaining_targets.binary_search(&node_id) {
+                remaining_targets.remove(index);
+                if remaining_targets.is_empty() {
+                    break;
+                }
+            }
+
+            let cost = workspace.node_cost[node_id as usize];
+            for edge_id in self.graph.outgoing_edge_ids(node_id) {
+                let edge = &self.graph.edges[edge_id as usize];
+                workspace.relax(
+                    edge.target_id,
+                    cost.saturating_add(edge.cost),
+                    Some(edge_id),
+                );
+            }
+        }
+
+        to_node_ids
+            .iter()
+            .map(|&to_node_id| workspace.cost(to_node_id))
+            .collect()
+    }
+
+    /// Returns the ids of all nodes that can be reached from `from_node_id` with a cost of at most
+    /// `max_cost`, in order of their cost. The search stops at the first node above `max_cost`, the
+    /// costs of the returned nodes can be read with `DijkstraWorkspace::cost`.
+    pub fn one_to_all_within<Q: MonotoneQueue>(
+        &self,
+        workspace: &mut DijkstraWorkspace<Q>,
+        from_node_id: u32,
+        max_cost: u32,
+    ) -> Vec<u32> {
+        workspace.clear();
+        workspace.relax(from_node_id, 0, None);
+
+        let mut node_ids = Vec::new();
+        while let Some(node_id) = workspace.queue.pop() {
+            if workspace.is_expanded[node_id as usize] {
+                continue;
+            }
+            let cost = workspace.node_cost[node_id as usize];
+            if cost > max_cost {
+                break;
+            }
+            workspace.is_expanded[node_id as usize] = true;
+            node_ids.push(node_id);
+
+            for edge_id in self.graph.outgoing_edge_ids(node_id) {
+                let edge = &self.graph.edges[edge_id as usize];
+                workspace.relax(
+                    edge.target_id,
+                    cost.saturating_add(edge.cost),
+                    Some(edge_id),
+                );
+            }
+        }
+
+        node_ids
+    }
+
+    /// Returns the cost from `from_node_id` to every node, or `u32::MAX` if a node is not
+    /// reachable.
+    pub fn one_to_all(&self, from_node_id: u32) -> Vec<u32> {
+        self.search_all(from_node_id, |node_id| {
+            self.graph
+                .outgoing_edge_ids(node_id)
+                .map(|edge_id| &self.graph.edges[edge_id as usize])
+                .map(|edge| (edge.target_id, edge.cost))
+        })
+    }
+
+    /// Returns the cost from every node to `to_node_id`, or `u32::MAX` if `to_node_id` is not
+    /// reachable from a node.
+    pub fn all_to_one(&self, to_node_id: u32) -> Vec<u32> {
+        self.search_all(to_node_id, |node_id| {
+            self.graph
+                .incoming_edge_ids(node_id)
+                .iter()
+                .map(|&edge_id| &self.graph.edges[edge_id as usize])
+                .map(|edge| (edge.source_id, edge.cost))
+        })
+    }
 
-        let mut edge_from_predecessor = vec![None; self.graph.nodes.len()];
-        let mut node_cost: Vec<Option<u32>> = vec![None; self.graph.nodes.len()];
+    fn search_all<I>(&self, start_node_id: u32, neighbours: impl Fn(u32) -> I) -> Vec<u32>
+    where
+        I: Iterator<Item = (u32, u32)>,
+    {
+        let mut queue = RadixHeap::new();
+
+        let mut node_cost = vec![u32::MAX; self.graph.nodes.len()];
         let mut is_expanded: Vec<bool> = vec![false; self.graph.nodes.len()];
 
-        node_cost[from_node_id as usize] = Some(0);
-        queue.insert(0, from_node_id);
+        node_cost[start_node_id as usize] = 0;
+        queue.insert(0, start_node_id);
 
         while let Some(node_id) = queue.pop() {
             if is_expanded[node_id as usize] {
                 continue;
             }
-            if node_id == to_node_id as u32 {
-                break;
-            }
             is_expanded[node_id as usize] = true;
 
-            (self.graph.edges_start_at[node_id as usize]
-                ..self.graph.edges_start_at[node_id as usize + 1])
-                .for_each(|edge_id| {
-                    let edge = &self.graph.edges[edge_id as usize];
-                    let alternative_cost = node_cost[node_id as usize].unwrap() + edge.cost;
-                    if alternative_cost < node_cost[edge.target_id as usize].unwrap_or(u32::MAX) {
-                        edge_from_predecessor[edge.target_id as usize] = Some(edge_id);
-                        node_cost[edge.target_id as usize] = Some(alternative_cost);
-                        queue.insert(alternative_cost, edge.target_id);
-                    }
-                });
+            for (neighbour_id, cost) in neighbours(node_id) {
+                let alternative_cost = node_cost[node_id as usize] + cost;
+                if alternative_cost < node_cost[neighbour_id as usize] {
+                    node_cost[neighbour_id as usize] = alternative_cost;
+                    queue.insert(alternative_cost, neighbour_id);
+                }
+            }
         }
 
-        (
-            edge_from_predecessor,
-            node_cost[to_node_id as usize].unwrap_or(u32::MAX),
-        )
+        node_cost
     }
 }
 
 pub fn dijkstra(graph: &Graph, from_node_id: u32, to_node_id: u32) -> (Vec<Option<u32>>, u32) {
-    let mut queue = BucketQueue::new(60_000);
+    let mut queue = RadixHeap::new();
 
     let mut edge_from_predecessor = vec![None; graph.nodes.len()];
     let mut node_cost: Vec<Option<u32>> = vec![None; graph.nodes.len()];
@@ -85,3 +335,82 @@ pub fn dijkstra(graph: &Graph, from_node_id: u32, to_node_id: u32) -> (Vec<Optio
         node_cost[to_node_id as usize].unwrap_or(u32::MAX),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use crate::routing::{graph::random_graph, Dijkstra, DijkstraWorkspace};
+
+    #[test]
+    fn route_via_visits_waypoints_in_order() {
+        let graph = random_graph(1_000, 5_000);
+        let dijkstra = Dijkstra::new(&graph);
+        let mut workspace = DijkstraWorkspace::new(graph.nodes.len());
+
+        let mut rng = StdRng::seed_from_u64(8);
+        for _ in 0..50 {
+            let waypoints: Vec<u32> = (0..4).map(|_| rng.gen_range(0..1_000)).collect();
+            let Some(legs) = dijkstra.route_via(&mut workspace, &waypoints) else {
+                continue;
+            };
+
+            assert_eq!(legs.len(), waypoints.len() - 1);
+            for (leg, waypoints) in legs.iter().zip(waypoints.windows(2)) {
+                let node_ids = leg.node_ids();
+                assert_eq!(node_ids.first(), Some(&waypoints[0]));
+                assert_eq!(node_ids.last(), Some(&waypoints[1]));
+                assert_eq!(leg.cost, dijkstra.dijkstra(waypoints[0], waypoints[1]).1);
+            }
+        }
+    }
+
+    #[test]
+    fn route_between_picks_cheapest_pair() {
+        let graph = random_graph(1_000, 5_000);
+        let dijkstra = Dijkstra::new(&graph);
+        let mut workspace = DijkstraWorkspace::new(graph.nodes.len());
+
+        let mut rng = StdRng::seed_from_u64(12);
+        for _ in 0..50 {
+            let mut connections = || -> Vec<(u32, u32)> {
+                (0..3)
+                    .map(|_| (rng.gen_range(0..1_000), rng.gen_range(0..5_000)))
+                    .collect()
+            };
+            let sources = connections();
+            let targets = connections();
+
+            let expected = sources
+                .iter()
+                .flat_map(|&(source, source_cost)| {
+                    targets.iter().map(move |&(target, target_cost)| {
+                        (source, source_cost, target, target_cost)
+                    })
+                })
+                .filter_map(|(source, source_cost, target, target_cost)| {
+                    let (_, cost) = dijkstra.dijkstra(source, target);
+                    (cost != u32::MAX).then(|| source_cost + cost + target_cost)
+                })
+                .min();
+
+            let route = dijkstra.route_between(&mut workspace, &sources, &targets);
+            let cost = route.map(|route| {
+                let source_cost = sources
+                    .iter()
+                    .filter(|&&(source, _)| source == route.start)
+                    .map(|&(_, cost)| cost)
+                    .min()
+                    .unwrap();
+                let target_cost = targets
+                    .iter()
+                    .filter(|&&(target, _)| target == route.end)
+                    .map(|&(_, cost)| cost)
+                    .min()
+                    .unwrap();
+                source_cost + route.cost + target_cost
+            });
+            assert_eq!(cost, expected);
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use std::cell::RefCell;
+
+use rayon::prelude::*;
+
+use super::{Dijkstra, DijkstraWorkspace, Graph};
+
+thread_local! {
+    /// Search state of the sources on this rayon worker thread, kept for later tables of a graph
+    /// with the same number of nodes.
+    static WORKSPACE: RefCell<Option<DijkstraWorkspace>> = const { RefCell::new(None) };
+}
+
+/// Computes the costs between all sources and all targets, e.g. for port to port distance
+/// tables. `costs[i][j]` is the cost from the i-th source to the j-th target, or `u32::MAX` if
+/// the target is not reachable.
+pub struct DistanceTable {
+    pub sources: Vec<u32>,
+    pub targets: Vec<u32>,
+    pub costs: Vec<Vec<u32>>,
+}
+
+impl DistanceTable {
+    /// Runs one one-to-many Dijkstra per source in parallel. Each rayon worker thread allocates
+    /// its own workspace once and reuses it for all of its sources, also of later tables.
+    pub fn new(graph: &Graph, sources: Vec<u32>, targets: Vec<u32>) -> DistanceTable {
+        let dijkstra = Dijkstra::new(graph);
+        let costs = sources
+            .par_iter()
+            .map(|&source| {
+                WORKSPACE.with(|workspace| {
+                    let mut workspace = workspace.borrow_mut();
+                    if workspace
+                        .as_ref()
+                        .is_none_or(|workspace| workspace.node_cost.len() != graph.nodes.len())
+                    {
+                        *workspace = Some(DijkstraWorkspace::new(graph.nodes.len()));
+                    }
+                    dijkstra.one_to_many(workspace.as_mut().unwrap(), source, &targets)
+                })
+            })
+            .collect();
+
+        DistanceTable {
+            sources,
+            targets,
+            costs,
+        }
+    }
+
+    /// Returns the cost from the i-th source to the j-th target.
+    pub fn cost(&self, i: usize, j: usize) -> u32 {
+        self.costs[i][j]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use crate::routing::{graph::random_graph, Dijkstra, DistanceTable};
+
+    #[test]
+    fn same_costs_as_dijkstra() {
+        let graph = random_graph(1_000, 3_000);
+        let dijkstra = Dijkstra::new(&graph);
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let sources: Vec<u32> = (0..20).map(|_| rng.gen_range(0..1_000)).collect();
+        let targets: Vec<u32> = (0..30).map(|_| rng.gen_range(0..1_000)).collect();
+        let table = DistanceTable::new(&graph, sources.clone(), targets.clone());
+
+        for (i, &source) in sources.iter().enumerate() {
+            for (j, &target) in targets.iter().enumerate() {
+                let (_, expected) = dijkstra.dijkstra(source, target);
+                assert_eq!(table.cost(i, j), expected);
+            }
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+
+use crate::geometry::{Arc, Linestring, Polygon};
+
+use super::Graph;
+
+/// Emission control areas, e.g. the North Sea, the Baltic or the North American coasts, where
+/// the more expensive low sulphur fuel is required. Every edge knows the meters it runs inside
+/// the zones, so queries can optimize a weighted sum of the distance and the distance inside.
+pub struct EmissionZones {
+    polygons: Vec<Polygon>,
+    /// Meters inside the zones by edge id, for the edges entering a zone.
+    edge_meters: HashMap<u32, f64>,
+}
+
+impl EmissionZones {
+    /// Measures the meters inside the zones of every edge. The zones should not overlap, the
+    /// meters inside overlapping zones are counted once per zone.
+    pub fn new(graph: &Graph, polygons: Vec<Polygon>) -> EmissionZones {
+        let mut edge_meters: HashMap<u32, f64> = HashMap::new();
+        for polygon in polygons.iter() {
+            for edge_id in graph.colliding_edge_ids(polygon) {
+                let meters = polygon.length_inside(&graph.edge_arc(edge_id));
+                *edge_meters.entry(edge_id).or_insert(0.0) += meters;
+            }
+        }
+
+        EmissionZones {
+            polygons,
+            edge_meters,
+        }
+    }
+
+    /// Returns the meters of the edge inside the zones.
+    pub fn edge_meters(&self, edge_id: u32) -> f64 {
+        self.edge_meters.get(&edge_id).copied().unwrap_or(0.0)
+    }
+
+    /// Returns the meters of the line inside the zones, e.g. of a smoothed route.
+    pub fn meters_inside(&self, linestring: &Linestring) -> f64 {
+        linestring
+            .points
+            .windows(2)
+            .map(|points| {
+                let arc = Arc::new(&points[0], &points[1]);
+                self.polygons
+                    .iter()
+                    .map(|polygon| polygon.length_inside(&arc))
+                    .sum::<f64>()
+            })
+            .sum()
+    }
+
+    /// Returns the cost of the edge for `Dijkstra::query_with_weights`, which is its cost, e.g.
+    /// `edge.cost`, plus `factor` times its meters inside the zones, e.g. 0.5 to make the zones
+    /// half again as expensive.
+    pub fn weight(&self, edge_id: u32, cost: u32, factor: f64) -> u32 {
+        let weight = cost as f64 + factor * self.edge_meters(edge_id);
+        weight.round().min(u32::MAX as f64) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use crate::{
+        geometry::{Point, Polygon},
+        routing::{
+            detour_area, detour_graph, random_graph, Dijkstra, DijkstraWorkspace, EmissionZones,
+            Route,
+        },
+    };
+
+    fn zone() -> Polygon {
+        let point = |lat, lon| Point::from_coordinate(lat, lon);
+        Polygon::new(vec![
+            point(10.0, 20.0),
+            point(10.0, 20.1),
+            point(10.1, 20.1),
+            point(10.1, 20.0),
+            point(10.0, 20.0),
+        ])
+    }
+
+    #[test]
+    fn routes_are_weighted_by_meters_inside() {
+        let graph = random_graph(1_000, 5_000);
+        let zones = EmissionZones::new(&graph, vec![zone()]);
+        let dijkstra = Dijkstra::new(&graph);
+        let mut workspace = DijkstraWorkspace::new(graph.nodes.len());
+
+        let mut rng = StdRng::seed_from_u64(22);
+        for _ in 0..20 {
+            let from = rng.gen_range(0..1_000);
+            let to = rng.gen_range(0..1_000);
+            let (_, cost) = dijkstra.dijkstra(from, to);
+            let weighted_cost =
+                dijkstra.query_with_weights(&mut workspace, from, to, |id, edge| {
+                    Some(zones.weight(id, edge.cost, 2.0))
+                });
+            let Some(route) = workspace.route(&graph, from, to) else {
+                assert_eq!(cost, u32::MAX);
+                continue;
+            };
+
+            // the weighted route is never shorter, but never longer inside the zones, up to the
+            // rounding of the weights
+            let meters_inside = |route: &Route| zones.meters_inside(&route.to_linestring(&graph));
+            assert!(route.cost >= cost);
+            assert!(weighted_cost >= cost);
+            dijkstra.query(&mut workspace, from, to);
+            let shortest = workspace.route(&graph, from, to).unwrap();
+            assert!(meters_inside(&route) <= meters_inside(&shortest) + route.edges.len() as f64);
+        }
+    }
+
+    #[test]
+    fn edge_meters_match_the_geometry() {
+        let graph = random_graph(1_000, 5_000);
+        let zones = EmissionZones::new(&graph, vec![zone()]);
+        let polygon = zone();
+        for edge_id in 0..graph.edges.len() as u32 {
+            let expected = polygon.length_inside(&graph.edge_arc(edge_id));
+            assert!((zones.edge_meters(edge_id) - expected).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn emission_zones_on_a_known_graph() {
+        let graph = detour_graph();
+        let zones = EmissionZones::new(&graph, vec![detour_area()]);
+        let dijkstra = Dijkstra::new(&graph);
+        let mut workspace = DijkstraWorkspace::new(graph.nodes.len());
+
+        // about half of the direct route is in the zone, the detour is about 1.4 times as long
+        let mut route = |factor| {
+            dijkstra.query_with_weights(&mut workspace, 0, 2, |id, edge| {
+                Some(zones.weight(id, edge.cost, factor))
+            });
+            workspace.route(&graph, 0, 2).unwrap().node_ids()
+        };
+        assert_eq!(route(0.5), vec![0, 1, 2]);
+        assert_eq!(route(2.0), vec![0, 3, 2]);
+    }
+}
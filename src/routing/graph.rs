@@ -1,151 +1,411 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
-use std::io::{self, BufRead};
+use std::io::{BufWriter, Write};
+use std::sync::OnceLock;
 
-#[derive(Clone)]
+use indicatif::ProgressIterator;
+use rayon::prelude::*;
+
+use crate::geometry::{radians_to_meter, Arc, Collides, Linestring, Planet, Point, Polygon};
+use crate::spatial_partition::PointSpatialPartition;
+use crate::text_reader::TextReader;
+use crate::{Error, Result};
+
+use super::{Clearance, GraphArray};
+
+#[derive(Clone, Copy)]
+#[repr(C)]
 pub struct Edge {
     pub source_id: u32,
     pub target_id: u32,
     pub cost: u32,
 }
 
-#[derive(Clone)]
-pub struct Node {
-    pub id: u32,
-    pub longitude: f32,
-    pub latitude: f32,
-}
+// an edge is three u32 without padding, as in the edge section of a graph file
+unsafe impl bytemuck::Zeroable for Edge {}
+unsafe impl bytemuck::Pod for Edge {}
 
+/// The graph of the sea, with the position of every node for snapping coordinates and
+/// converting paths, and the edges in adjacency arrays for routing. The id of a node is its
+/// index in `nodes`. The adjacency arrays may be mapped from a graph file, see `GraphFile`.
 pub struct Graph {
-    pub nodes: Vec<Node>,
-    pub edges: Vec<Edge>,
-    pub edges_start_at: Vec<u32>,
+    pub nodes: Vec<Point>,
+    pub edges: GraphArray<Edge>,
+    pub edges_start_at: GraphArray<u32>,
+    /// Ids of the edges sorted by their target, e.g. the reverse adjacency array.
+    pub incoming_edges: GraphArray<u32>,
+    pub incoming_edges_start_at: GraphArray<u32>,
+    /// The depth and height limits of each edge, by edge id.
+    pub clearances: GraphArray<Clearance>,
+    /// Index of the nodes for nearest node lookups, built on the first lookup.
+    point_grid: OnceLock<PointSpatialPartition>,
+    /// Length of the longest edge in meters, computed on the first use.
+    max_edge_length: OnceLock<f64>,
 }
 
 impl Graph {
-    pub fn from_file(filename: &str) -> Graph {
-        let file = File::open(filename).unwrap();
-        let reader = io::BufReader::new(file);
-
-        let mut lines = reader.lines();
-        let number_of_nodes: usize = lines.by_ref().next().unwrap().unwrap().parse().unwrap();
-        let number_of_edges: usize = lines.by_ref().next().unwrap().unwrap().parse().unwrap();
-
-        let nodes: Vec<Node> = lines
-            .by_ref()
-            .take(number_of_nodes)
-            .map(|node_line| {
-                let node_line = node_line.unwrap();
-                let mut values = node_line.split_whitespace();
-                let node_id: u32 = values.next().unwrap().parse().unwrap();
-                //let _node_id2: usize = values.next().unwrap().parse().unwrap();
-                let latitude: f32 = values.next().unwrap().parse().unwrap();
-                let longitude: f32 = values.next().unwrap().parse().unwrap();
-                // let _elevation: f32 = values.next().unwrap().parse().unwrap();
-
-                Node {
-                    id: node_id,
-                    latitude,
-                    longitude,
-                }
-            })
-            .collect();
+    pub fn from_file(path: &str) -> Result<Graph> {
+        let mut reader = TextReader::open(path)?;
 
-        let edges: Vec<Edge> = lines
-            .by_ref()
-            .take(number_of_edges)
-            .map(|edge_line| {
-                let line = edge_line.unwrap();
-                let mut values = line.split_whitespace();
-                let source_id: u32 = values.next().unwrap().parse().unwrap();
-                let target_id: u32 = values.next().unwrap().parse().unwrap();
-                let cost: u32 = values.next().unwrap().parse().unwrap();
-                // let _type: u32 = values.next().unwrap().parse().unwrap();
-                // let _maxspeed: usize = values.next().unwrap().parse().unwrap();
-
-                Edge {
-                    source_id,
-                    target_id,
-                    cost,
-                }
-            })
-            .collect();
+        let num_nodes: usize = reader.next_value()?;
+        let num_edges: usize = reader.next_value()?;
+
+        println!("reading {} nodes", num_nodes);
+        let mut nodes = Vec::with_capacity(num_nodes);
+        for i in (0..num_nodes).progress() {
+            let line = reader.next_line()?;
+            let mut values = line.split_whitespace();
+            let id: usize = reader.parse(values.next())?;
+            if id != i {
+                return Err(Error::format(
+                    path,
+                    format!("node {} is listed as node {}", i, id),
+                ));
+            }
+            let latitude = reader.parse(values.next())?;
+            let longitude = reader.parse(values.next())?;
+            nodes.push(Point::try_from_coordinate(latitude, longitude)?);
+        }
+
+        println!("reading {} edges", num_edges);
+        let mut edges = Vec::with_capacity(num_edges);
+        let mut clearances = Vec::with_capacity(num_edges);
+        for _ in (0..num_edges).progress() {
+            let line = reader.next_line()?;
+            let mut values = line.split_whitespace();
+            let source_id: u32 = reader.parse(values.next())?;
+            let target_id: u32 = reader.parse(values.next())?;
+            let cost = reader.parse(values.next())?;
+            if source_id as usize >= num_nodes || target_id as usize >= num_nodes {
+                return Err(Error::format(
+                    path,
+                    format!("edge {} -> {} has an unknown node", source_id, target_id),
+                ));
+            }
+
+            // depth and height are optional columns, missing for graphs without limits
+            let clearance = match values.next() {
+                Some(depth) => Clearance {
+                    depth: reader.parse(Some(depth))?,
+                    height: reader.parse(values.next())?,
+                },
+                None => Clearance::UNLIMITED,
+            };
 
-        // make bidrectional
-        let mut edge_map = HashMap::new();
-        edges.iter().for_each(|edge| {
-            if edge.cost
-                < *edge_map
-                    .get(&(edge.source_id, edge.target_id))
-                    .unwrap_or(&u32::MAX)
-            {
-                edge_map.insert((edge.source_id, edge.target_id), edge.cost);
+            edges.push(Edge {
+                source_id,
+                target_id,
+                cost,
+            });
+            clearances.push(clearance);
+        }
+
+        Ok(Graph::with_clearances(nodes, edges, clearances))
+    }
+
+    /// Writes the graph as a `.fmi` file, which `from_file` reads back into the same graph. If an
+    /// edge has a limited clearance, the depth and height of every edge are added as columns.
+    pub fn to_file(&self, path: &str) {
+        let with_clearances = self.clearances.iter().any(Clearance::is_limited);
+        let mut writer = BufWriter::new(File::create(path).unwrap());
+        writeln!(writer, "{}", self.nodes.len()).unwrap();
+        writeln!(writer, "{}", self.edges.len()).unwrap();
+
+        println!("writing {} nodes to file", self.nodes.len());
+        for (id, point) in self.nodes.iter().enumerate().progress() {
+            writeln!(writer, "{} {} {}", id, point.latitude(), point.longitude()).unwrap();
+        }
+
+        println!("writing {} edges to file", self.edges.len());
+        for (edge, clearance) in self.edges.iter().zip(self.clearances.iter()).progress() {
+            write!(
+                writer,
+                "{} {} {}",
+                edge.source_id, edge.target_id, edge.cost
+            )
+            .unwrap();
+            if with_clearances {
+                write!(writer, " {} {}", clearance.depth, clearance.height).unwrap();
+            }
+            writeln!(writer).unwrap();
+        }
+        writer.flush().unwrap();
+    }
+
+    /// Creates a graph from nodes and edges. Parallel edges are merged, keeping the cheapest one,
+    /// and the edges are sorted by source to build the offset array `edges_start_at`.
+    pub fn new(nodes: Vec<Point>, edges: Vec<Edge>) -> Graph {
+        let clearances = vec![Clearance::UNLIMITED; edges.len()];
+        Graph::with_clearances(nodes, edges, clearances)
+    }
+
+    /// Same as `new`, with the clearance of each edge. A merged parallel edge keeps the clearance
+    /// of the cheapest edge.
+    pub fn with_clearances(
+        nodes: Vec<Point>,
+        edges: Vec<Edge>,
+        clearances: Vec<Clearance>,
+    ) -> Graph {
+        assert_eq!(edges.len(), clearances.len());
+        // remove parallel edges, keeping the cheapest
+        let mut edge_map: HashMap<(u32, u32), (u32, Clearance)> = HashMap::new();
+        edges.iter().zip(clearances).for_each(|(edge, clearance)| {
+            let key = (edge.source_id, edge.target_id);
+            if edge.cost < edge_map.get(&key).map_or(u32::MAX, |&(cost, _)| cost) {
+                edge_map.insert(key, (edge.cost, clearance));
             }
-            // if edge.cost
-            //     < *edge_map
-            //         .get(&(edge.target_id, edge.source_id))
-            //         .unwrap_or(&u32::MAX)
-            // {
-            //     edge_map.insert((edge.target_id, edge.source_id), edge.cost);
-            // }
         });
         let mut edges: Vec<_> = edge_map
             .iter()
-            .map(|(k, v)| Edge {
-                source_id: k.0,
-                target_id: k.1,
-                cost: *v,
+            .map(|(k, &(cost, clearance))| {
+                let edge = Edge {
+                    source_id: k.0,
+                    target_id: k.1,
+                    cost,
+                };
+                (edge, clearance)
             })
             .collect();
+        edges.sort_unstable_by_key(|(edge, _)| (edge.source_id, edge.target_id));
+        let (edges, clearances): (Vec<_>, Vec<_>) = edges.into_iter().unzip();
+
+        let mut edges_start_at: Vec<u32> = vec![0; nodes.len() + 1];
+        edges
+            .iter()
+            .for_each(|edge| edges_start_at[edge.source_id as usize + 1] += 1);
+        for node_id in 0..nodes.len() {
+            edges_start_at[node_id + 1] += edges_start_at[node_id];
+        }
 
-        let mut edges_start_for_node: Vec<u32> = vec![0; edges.len() + 1];
+        let mut incoming_edges: Vec<u32> = (0..edges.len() as u32).collect();
+        incoming_edges.sort_by_key(|&edge_id| edges[edge_id as usize].target_id);
+        let mut incoming_edges_start_at: Vec<u32> = vec![0; nodes.len() + 1];
+        edges
+            .iter()
+            .for_each(|edge| incoming_edges_start_at[edge.target_id as usize + 1] += 1);
+        for node_id in 0..nodes.len() {
+            incoming_edges_start_at[node_id + 1] += incoming_edges_start_at[node_id];
+        }
 
-        // temporarrly adding a node in order to generate the list
-        edges.push(Edge {
-            source_id: edges.len() as u32,
-            target_id: 0,
-            cost: 0,
-        });
-        edges.sort_unstable_by_key(|edge| edge.source_id);
-
-        let mut current = 0;
-        for (i, edge) in edges.iter().enumerate() {
-            if edge.source_id != current {
-                for index in (current + 1)..=edge.source_id {
-                    edges_start_for_node[index as usize] = i as u32;
-                }
-                current = edge.source_id;
-            }
+        Graph::from_adjacency_arrays(
+            nodes,
+            edges.into(),
+            edges_start_at.into(),
+            incoming_edges.into(),
+            incoming_edges_start_at.into(),
+            clearances.into(),
+        )
+    }
+
+    /// Creates a graph from arcs between the points, each arc costs its length in meters.
+    pub fn from_arcs(points: Vec<Point>, arcs: &[Arc]) -> Graph {
+        let mut point_id_map = HashMap::new();
+        for (i, point) in points.iter().enumerate() {
+            point_id_map.insert(*point, i as u32);
         }
-        edges.pop();
 
+        let edges = arcs
+            .iter()
+            .map(|arc| Edge {
+                source_id: point_id_map[arc.from()],
+                target_id: point_id_map[arc.to()],
+                cost: radians_to_meter(arc.central_angle()) as u32,
+            })
+            .collect();
+        Graph::new(points, edges)
+    }
+
+    /// Creates a graph from arrays that are already sorted and deduplicated like in `new`.
+    pub(crate) fn from_adjacency_arrays(
+        nodes: Vec<Point>,
+        edges: GraphArray<Edge>,
+        edges_start_at: GraphArray<u32>,
+        incoming_edges: GraphArray<u32>,
+        incoming_edges_start_at: GraphArray<u32>,
+        clearances: GraphArray<Clearance>,
+    ) -> Graph {
         Graph {
-            nodes: nodes.clone(),
-            edges: edges.clone(),
-            edges_start_at: edges_start_for_node.clone(),
+            nodes,
+            edges,
+            edges_start_at,
+            incoming_edges,
+            incoming_edges_start_at,
+            clearances,
+            point_grid: OnceLock::new(),
+            max_edge_length: OnceLock::new(),
         }
     }
+
+    /// Returns a graph of the same nodes with only the edges for which `keep` returns true, given
+    /// the id of the edge.
+    pub fn filter_edges(&self, keep: impl Fn(u32) -> bool) -> Graph {
+        let (edges, clearances) = (0..self.edges.len() as u32)
+            .filter(|&edge_id| keep(edge_id))
+            .map(|edge_id| {
+                (
+                    self.edges[edge_id as usize],
+                    self.clearances[edge_id as usize],
+                )
+            })
+            .unzip();
+        Graph::with_clearances(self.nodes.clone(), edges, clearances)
+    }
+
+    /// Returns the ids of the edges leaving the node.
+    pub fn outgoing_edge_ids(&self, node_id: u32) -> std::ops::Range<u32> {
+        self.edges_start_at[node_id as usize]..self.edges_start_at[node_id as usize + 1]
+    }
+
+    /// Returns the ids of the edges entering the node.
+    pub fn incoming_edge_ids(&self, node_id: u32) -> &[u32] {
+        &self.incoming_edges[self.incoming_edges_start_at[node_id as usize] as usize
+            ..self.incoming_edges_start_at[node_id as usize + 1] as usize]
+    }
+
+    /// Returns the arc from the source to the target of the edge.
+    pub fn edge_arc(&self, edge_id: u32) -> Arc {
+        let edge = &self.edges[edge_id as usize];
+        Arc::new(
+            &self.nodes[edge.source_id as usize],
+            &self.nodes[edge.target_id as usize],
+        )
+    }
+
+    /// Returns the length of the longest edge in meters. Any edge crossing a geometry has an end
+    /// within this distance of it, e.g. to find the edges crossing an area with `within`.
+    pub fn max_edge_length(&self) -> f64 {
+        *self.max_edge_length.get_or_init(|| {
+            (0..self.edges.len() as u32)
+                .map(|edge_id| radians_to_meter(self.edge_arc(edge_id).central_angle()))
+                .fold(0.0, f64::max)
+        })
+    }
+
+    /// Returns the ids of the edges colliding with the polygon, e.g. the edges in an area. The
+    /// polygon has to be smaller than a hemisphere.
+    pub fn colliding_edge_ids(&self, polygon: &Polygon) -> Vec<u32> {
+        // every edge colliding with the polygon has an end within this distance of its center
+        let (center, radius) = polygon.bounding_circle();
+        self.within(&center, radius + self.max_edge_length())
+            .into_par_iter()
+            .flat_map_iter(|(node_id, _)| {
+                self.outgoing_edge_ids(node_id)
+                    .chain(self.incoming_edge_ids(node_id).iter().copied())
+            })
+            .filter(|&edge_id| self.edge_arc(edge_id).collides(polygon))
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// Returns the edges as arcs, e.g. to draw the graph.
+    pub fn to_planet(&self) -> Planet {
+        let mut planet = Planet::new();
+        planet.arcs = (0..self.edges.len() as u32)
+            .map(|edge_id| self.edge_arc(edge_id))
+            .collect();
+        planet
+    }
+
+    /// Returns the index of the nodes, building it if this is the first lookup.
+    pub fn point_grid(&self) -> &PointSpatialPartition {
+        self.point_grid.get_or_init(|| {
+            println!("building point index");
+            let mut point_grid = PointSpatialPartition::new_root(32);
+            point_grid.add_points(&self.nodes);
+            point_grid
+        })
+    }
+
+    /// Returns the id of the node closest to the coordinate and its distance in meters, or an
+    /// error if the coordinate is invalid or the graph has no nodes.
+    pub fn nearest(&self, lon: f64, lat: f64) -> Result<(u32, f64)> {
+        let point = Point::try_from_coordinate(lat, lon)?;
+        self.k_nearest(&point, 1)
+            .first()
+            .copied()
+            .ok_or_else(|| Error::InvalidArgument("the graph has no nodes".to_string()))
+    }
+
+    /// Returns the ids of the `k` nodes closest to the point and their distance in meters, the
+    /// closest first.
+    pub fn k_nearest(&self, point: &Point, k: usize) -> Vec<(u32, f64)> {
+        self.point_grid().k_nearest(point, k)
+    }
+
+    /// Returns the ids of all nodes within `radius` meters of the point and their distance in
+    /// meters.
+    pub fn within(&self, point: &Point, radius: f64) -> Vec<(u32, f64)> {
+        self.point_grid().within(point, radius)
+    }
+
+    /// Returns the positions of the nodes along a path.
+    pub fn convert_path(&self, path: &[u32]) -> Vec<Point> {
+        path.iter().map(|&id| self.nodes[id as usize]).collect()
+    }
 }
 
 pub struct Route {
     pub start: u32,
     pub end: u32,
     pub cost: u32,
+    /// The edges of the route, in order from start to end.
     pub edges: Vec<Edge>,
 }
 
+impl Route {
+    /// Returns the ids of all nodes along the route, including start and end.
+    pub fn node_ids(&self) -> Vec<u32> {
+        let mut node_ids = vec![self.start];
+        node_ids.extend(self.edges.iter().map(|edge| edge.target_id));
+        node_ids
+    }
+
+    /// Returns the positions of the nodes along the route, e.g. to export it.
+    pub fn to_linestring(&self, graph: &Graph) -> Linestring {
+        Linestring::new(graph.convert_path(&self.node_ids()))
+    }
+}
+
 pub fn get_route(
     graph: &Graph,
     start: u32,
     end: u32,
     used_edges: Vec<Option<u32>>,
+) -> Option<Route> {
+    get_route_by(graph, start, end, |node_id| used_edges[node_id as usize])
+}
+
+/// Returns the route along the edges, given by their ids in path order, e.g. the unpacked edges
+/// of a `ContractionHierarchy::query`.
+pub fn get_route_of_edges(graph: &Graph, start: u32, end: u32, edge_ids: &[u32]) -> Route {
+    let edges: Vec<Edge> = edge_ids
+        .iter()
+        .map(|&edge_id| &graph.edges[edge_id as usize])
+        .cloned()
+        .collect();
+    Route {
+        start,
+        end,
+        cost: edges.iter().map(|edge| edge.cost).sum(),
+        edges,
+    }
+}
+
+/// Same as `get_route`, but looks up the edge leading to a node with `edge_from_predecessor`
+/// instead of indexing a vector.
+pub fn get_route_by(
+    graph: &Graph,
+    start: u32,
+    end: u32,
+    edge_from_predecessor: impl Fn(u32) -> Option<u32>,
 ) -> Option<Route> {
     let mut edges: Vec<Edge> = Vec::new();
     let mut current: u32 = end;
 
-    while let Some(edge_index) = used_edges[current as usize] {
+    while let Some(edge_index) = edge_from_predecessor(current) {
         current = graph.edges[edge_index as usize].source_id;
-        edges.push(graph.edges[edge_index as usize].clone());
+        edges.push(graph.edges[edge_index as usize]);
         if current == start {
             break;
         }
@@ -154,6 +414,7 @@ pub fn get_route(
     if current != start {
         return None;
     }
+    edges.reverse();
 
     Some(Route {
         start,
@@ -162,3 +423,226 @@ pub fn get_route(
         edges,
     })
 }
+
+/// Creates a random graph for tests. The nodes are spread over a small patch of ocean and every
+/// edge costs at least the great circle distance between its nodes. The graphs are seeded, so
+/// the same sizes give the same graph.
+#[cfg(test)]
+pub(crate) fn random_graph(num_nodes: u32, num_edges: u32) -> Graph {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    let mut rng = StdRng::seed_from_u64(u64::from(num_nodes) << 32 | u64::from(num_edges));
+    let nodes: Vec<Point> = (0..num_nodes)
+        .map(|_| Point::from_coordinate(rng.gen_range(10.0..10.2), rng.gen_range(20.0..20.2)))
+        .collect();
+
+    let edges = (0..num_edges)
+        .map(|_| {
+            let source_id = rng.gen_range(0..num_nodes);
+            let target_id = rng.gen_range(0..num_nodes);
+            let arc = Arc::new(&nodes[source_id as usize], &nodes[target_id as usize]);
+            let cost = radians_to_meter(arc.central_angle()) * rng.gen_range(1.0..1.5);
+            Edge {
+                source_id,
+                target_id,
+                cost: cost.ceil() as u32,
+            }
+        })
+        .collect();
+
+    Graph::new(nodes, edges)
+}
+
+/// Creates a small graph with the edge cases of routing for tests: edges that cost nothing, also
+/// in a cycle, a node without edges and a node that reaches the others but cannot be reached.
+#[cfg(test)]
+pub(crate) fn edge_case_graph() -> Graph {
+    let nodes = (0..6)
+        .map(|i| Point::from_coordinate(10.0 + i as f64 * 0.01, 20.0))
+        .collect();
+    let edge = |source_id, target_id, cost| Edge {
+        source_id,
+        target_id,
+        cost,
+    };
+    let edges = vec![
+        edge(0, 1, 0),
+        edge(1, 2, 5),
+        edge(0, 2, 7),
+        edge(2, 3, 0),
+        edge(3, 2, 0),
+        edge(3, 0, 4),
+        edge(5, 0, 3),
+    ];
+    Graph::new(nodes, edges)
+}
+
+/// Creates a small graph for tests with two routes both ways between node 0 and node 2: the
+/// direct route east through node 1 and a longer detour through node 3 north of it. Only the
+/// direct route runs through `detour_area`.
+#[cfg(test)]
+pub(crate) fn detour_graph() -> Graph {
+    let points = vec![
+        Point::from_coordinate(10.0, 20.0),
+        Point::from_coordinate(10.0, 20.1),
+        Point::from_coordinate(10.0, 20.2),
+        Point::from_coordinate(10.1, 20.1),
+    ];
+    let arcs: Vec<Arc> = [(0, 1), (1, 2), (0, 3), (3, 2)]
+        .into_iter()
+        .flat_map(|(a, b)| {
+            [
+                Arc::new(&points[a], &points[b]),
+                Arc::new(&points[b], &points[a]),
+            ]
+        })
+        .collect();
+    Graph::from_arcs(points, &arcs)
+}
+
+/// The area around node 1 of `detour_graph`, which the detour keeps out of.
+#[cfg(test)]
+pub(crate) fn detour_area() -> Polygon {
+    let point = |lat, lon| Point::from_coordinate(lat, lon);
+    Polygon::new(vec![
+        point(9.97, 20.05),
+        point(9.97, 20.15),
+        point(10.03, 20.15),
+        point(10.03, 20.05),
+        point(9.97, 20.05),
+    ])
+}
+
+/// Asserts that `query` finds the cost of `Dijkstra::dijkstra` between pairs of nodes, every
+/// pair of a small graph and seeded random pairs of a large one, some of them from a node to
+/// itself. `query` returns the cost and the route it found, which must have that cost.
+#[cfg(test)]
+pub(crate) fn assert_same_cost_as_dijkstra(
+    graph: &Graph,
+    mut query: impl FnMut(u32, u32) -> (u32, Option<Route>),
+) {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::Dijkstra;
+
+    let num_nodes = graph.nodes.len() as u32;
+    let pairs: Vec<(u32, u32)> = if num_nodes <= 20 {
+        (0..num_nodes)
+            .flat_map(|from| (0..num_nodes).map(move |to| (from, to)))
+            .collect()
+    } else {
+        let mut rng = StdRng::seed_from_u64(1);
+        (0..200)
+            .map(|i| {
+                let from = rng.gen_range(0..num_nodes);
+                let to = if i % 20 == 0 {
+                    from
+                } else {
+                    rng.gen_range(0..num_nodes)
+                };
+                (from, to)
+            })
+            .collect()
+    };
+
+    let dijkstra = Dijkstra::new(graph);
+    for (from, to) in pairs {
+        let (_, expected) = dijkstra.dijkstra(from, to);
+        let (cost, route) = query(from, to);
+        assert_eq!(cost, expected, "wrong cost from {} to {}", from, to);
+        if cost == u32::MAX {
+            continue;
+        }
+        let route = route.unwrap_or_else(|| panic!("no route from {} to {}", from, to));
+        assert_eq!((route.start, route.end), (from, to));
+        assert_eq!(route.cost, cost, "wrong route from {} to {}", from, to);
+        assert!(route
+            .edges
+            .windows(2)
+            .all(|pair| pair[0].target_id == pair[1].source_id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        geometry::Point,
+        routing::{edge_case_graph, random_graph, Clearance, Dijkstra, Edge, Graph},
+        TestFile,
+    };
+
+    #[test]
+    fn file_round_trip() {
+        let graph = random_graph(500, 2_000);
+        let clearances = (0..graph.edges.len())
+            .map(|edge_id| match edge_id % 10 {
+                0 => Clearance {
+                    depth: 8.5,
+                    height: f32::INFINITY,
+                },
+                _ => Clearance::UNLIMITED,
+            })
+            .collect();
+        let graph = Graph::with_clearances(graph.nodes, graph.edges.to_vec(), clearances);
+
+        let file = TestFile::empty("file_round_trip.fmi");
+        graph.to_file(file.path());
+        let loaded = Graph::from_file(file.path()).unwrap();
+
+        assert_eq!(loaded.edges_start_at, graph.edges_start_at);
+        assert_eq!(loaded.incoming_edges, graph.incoming_edges);
+        assert_eq!(loaded.clearances, graph.clearances);
+        for (edge, loaded_edge) in graph.edges.iter().zip(loaded.edges.iter()) {
+            assert_eq!(
+                (edge.source_id, edge.target_id, edge.cost),
+                (
+                    loaded_edge.source_id,
+                    loaded_edge.target_id,
+                    loaded_edge.cost
+                )
+            );
+        }
+        for (point, loaded_point) in graph.nodes.iter().zip(loaded.nodes.iter()) {
+            assert!(point.is_approximately_equal(loaded_point));
+        }
+    }
+
+    #[test]
+    fn edge_case_costs() {
+        let graph = edge_case_graph();
+        let dijkstra = Dijkstra::new(&graph);
+        assert_eq!(dijkstra.dijkstra(0, 2).1, 5);
+        assert_eq!(dijkstra.dijkstra(0, 3).1, 5);
+        assert_eq!(dijkstra.dijkstra(3, 1).1, 4);
+        assert_eq!(dijkstra.dijkstra(2, 2).1, 0);
+        assert_eq!(dijkstra.dijkstra(0, 4).1, u32::MAX);
+        assert_eq!(dijkstra.dijkstra(0, 5).1, u32::MAX);
+        assert_eq!(dijkstra.dijkstra(5, 3).1, 8);
+    }
+
+    #[test]
+    fn nearest_on_empty_graph_is_an_error() {
+        let graph = Graph::new(Vec::new(), Vec::new());
+        assert!(graph.nearest(20.0, 10.0).is_err());
+        assert!(graph.nearest(200.0, 10.0).is_err());
+    }
+
+    #[test]
+    fn parallel_edges_are_merged() {
+        let nodes = vec![
+            Point::from_coordinate(0.0, 0.0),
+            Point::from_coordinate(0.0, 0.1),
+        ];
+        let edge = |cost| Edge {
+            source_id: 0,
+            target_id: 1,
+            cost,
+        };
+        let graph = Graph::new(nodes, vec![edge(20), edge(10), edge(30)]);
+
+        assert_eq!(graph.edges.len(), 1);
+        assert_eq!(graph.edges[0].cost, 10);
+        assert_eq!(graph.nearest(0.09, 0.0).unwrap().0, 1);
+        assert!(graph.nearest(0.0, 91.0).is_err());
+    }
+}
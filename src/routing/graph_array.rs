@@ -0,0 +1,52 @@
+use std::{fmt, ops::Deref, ops::Range, sync::Arc};
+
+use bytemuck::Pod;
+use memmap2::Mmap;
+
+/// An array of a `Graph`, either owned or a section of a memory-mapped graph file, which the
+/// graph then routes on without copying it. Both dereference to a slice.
+#[derive(Clone)]
+pub enum GraphArray<T> {
+    Vec(Vec<T>),
+    /// The bytes of the array in the mapped file.
+    Mapped(Arc<Mmap>, Range<usize>),
+}
+
+impl<T: Pod> GraphArray<T> {
+    /// Returns the array in the bytes of the mapped file. Panics if they are not aligned for `T`
+    /// or their length is not a multiple of its size.
+    pub(crate) fn mapped(mmap: &Arc<Mmap>, bytes: Range<usize>) -> GraphArray<T> {
+        // checks the alignment and length once, so dereferencing does not panic
+        let _: &[T] = bytemuck::cast_slice(&mmap[bytes.clone()]);
+        GraphArray::Mapped(mmap.clone(), bytes)
+    }
+}
+
+impl<T: Pod> Deref for GraphArray<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        match self {
+            GraphArray::Vec(vec) => vec,
+            GraphArray::Mapped(mmap, bytes) => bytemuck::cast_slice(&mmap[bytes.clone()]),
+        }
+    }
+}
+
+impl<T> From<Vec<T>> for GraphArray<T> {
+    fn from(vec: Vec<T>) -> GraphArray<T> {
+        GraphArray::Vec(vec)
+    }
+}
+
+impl<T: Pod + PartialEq> PartialEq for GraphArray<T> {
+    fn eq(&self, other: &GraphArray<T>) -> bool {
+        **self == **other
+    }
+}
+
+impl<T: Pod + fmt::Debug> fmt::Debug for GraphArray<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
@@ -0,0 +1,330 @@
+use std::{collections::HashMap, str::FromStr};
+
+use crate::{
+    geometry::{geojson_outlines, read_geojson_features, Arc, Collides, Polygon},
+    Error, Result,
+};
+
+use super::Graph;
+
+/// The ice class of a vessel, from the weakest to the strongest: the Finnish-Swedish classes for
+/// first-year ice in the Baltic, then the IACS polar classes from PC7 for summer and autumn
+/// operation in thin first-year ice to PC1 for year-round operation in all polar waters. 1A Super
+/// is ranked below PC7, the classes are roughly equal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum IceClass {
+    Baltic1C,
+    Baltic1B,
+    Baltic1A,
+    Baltic1ASuper,
+    Pc7,
+    Pc6,
+    Pc5,
+    Pc4,
+    Pc3,
+    Pc2,
+    Pc1,
+}
+
+impl FromStr for IceClass {
+    type Err = Error;
+
+    /// Parses the notation of the class, e.g. `PC5`, `1A Super` or `1AS`, ignoring case and
+    /// spaces.
+    fn from_str(ice_class: &str) -> Result<IceClass> {
+        let notation: String = ice_class
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+            .to_uppercase();
+        match notation.as_str() {
+            "1C" => Ok(IceClass::Baltic1C),
+            "1B" => Ok(IceClass::Baltic1B),
+            "1A" => Ok(IceClass::Baltic1A),
+            "1AS" | "1ASUPER" => Ok(IceClass::Baltic1ASuper),
+            "PC7" => Ok(IceClass::Pc7),
+            "PC6" => Ok(IceClass::Pc6),
+            "PC5" => Ok(IceClass::Pc5),
+            "PC4" => Ok(IceClass::Pc4),
+            "PC3" => Ok(IceClass::Pc3),
+            "PC2" => Ok(IceClass::Pc2),
+            "PC1" => Ok(IceClass::Pc1),
+            _ => Err(Error::InvalidArgument(format!(
+                "unknown ice class {}",
+                ice_class
+            ))),
+        }
+    }
+}
+
+/// Returns the month of the unix time in seconds, from 1 for January to 12 for December, in UTC.
+pub fn month_of(time: f64) -> u32 {
+    // the civil date of the days since 1970-01-01, shifted to eras of 400 years starting in March
+    let days = (time / 86_400.0).floor() as i64 + 719_468;
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    if month < 10 {
+        month as u32 + 3
+    } else {
+        month as u32 - 9
+    }
+}
+
+/// The extent of sea ice in some months of the year, e.g. of a monthly or seasonal ice chart.
+pub struct IceLimit {
+    pub polygon: Polygon,
+    /// Bit `i` is set if the ice reaches the limit in month `i + 1`.
+    pub months: u16,
+    /// The weakest ice class that may enter the ice, `None` if it is closed to all vessels.
+    pub ice_class: Option<IceClass>,
+}
+
+impl IceLimit {
+    pub fn is_active(&self, month: u32) -> bool {
+        self.months & (1 << (month - 1)) != 0
+    }
+
+    /// Returns true if a vessel of the ice class may enter the ice.
+    pub fn admits(&self, ice_class: Option<IceClass>) -> bool {
+        match (self.ice_class, ice_class) {
+            (Some(required), Some(ice_class)) => ice_class >= required,
+            _ => false,
+        }
+    }
+
+    /// Reads the ice limits of a GeoJSON file, the Polygons of its features with a `months`
+    /// property, the months from 1 to 12 the ice reaches the limit, and an optional `ice_class`
+    /// property, e.g. `"PC6"`. A season is given by its months. Holes of the polygons are
+    /// ignored and other geometries are skipped.
+    pub fn from_geojson_file(path: &str) -> Result<Vec<IceLimit>> {
+        let mut limits = Vec::new();
+        for (i, feature) in read_geojson_features(path)?.into_iter().enumerate() {
+            let outlines = match &feature.geometry {
+                Some(geometry) => geojson_outlines(path, &geometry.value)?,
+                None => Vec::new(),
+            };
+            if outlines.is_empty() {
+                continue;
+            }
+            let months = feature
+                .property("months")
+                .and_then(|months| months.as_array())
+                .and_then(|months| {
+                    months
+                        .iter()
+                        .try_fold(0u16, |mask, month| match month.as_u64() {
+                            Some(month @ 1..=12) => Some(mask | 1 << (month - 1)),
+                            _ => None,
+                        })
+                })
+                .ok_or_else(|| {
+                    Error::format(path, format!("feature {} has no months from 1 to 12", i))
+                })?;
+            let ice_class = match feature.property("ice_class") {
+                None => None,
+                Some(ice_class) => Some(
+                    ice_class
+                        .as_str()
+                        .ok_or_else(|| {
+                            Error::format(path, format!("feature {} has no ice class", i))
+                        })?
+                        .parse()
+                        .map_err(|error: Error| Error::format(path, error.to_string()))?,
+                ),
+            };
+            for outline in outlines {
+                limits.push(IceLimit {
+                    polygon: Polygon::new(outline),
+                    months,
+                    ice_class,
+                });
+            }
+        }
+
+        Ok(limits)
+    }
+}
+
+/// The edges in sea ice by month, e.g. to close the Northern Sea Route in winter but use it in
+/// summer. Vessels with a sufficient ice class may enter the ice, usually at a penalty for the
+/// slower passage.
+pub struct IceZones {
+    limits: Vec<IceLimit>,
+    /// The indices of the limits colliding with an edge, by edge id.
+    edge_limits: HashMap<u32, Vec<usize>>,
+}
+
+impl IceZones {
+    pub fn new(graph: &Graph, limits: Vec<IceLimit>) -> IceZones {
+        let mut edge_limits: HashMap<u32, Vec<usize>> = HashMap::new();
+        for (i, limit) in limits.iter().enumerate() {
+            for edge_id in graph.colliding_edge_ids(&limit.polygon) {
+                edge_limits.entry(edge_id).or_default().push(i);
+            }
+        }
+
+        IceZones {
+            limits,
+            edge_limits,
+        }
+    }
+
+    /// Returns the limits the edge collides with.
+    pub fn limits(&self, edge_id: u32) -> impl Iterator<Item = &IceLimit> {
+        self.edge_limits
+            .get(&edge_id)
+            .into_iter()
+            .flatten()
+            .map(|&i| &self.limits[i])
+    }
+
+    /// Returns the cost of the edge for `Dijkstra::query_with_weights` in the month, given its
+    /// cost in open water, e.g. `edge.cost`. Edges in ice are blocked unless all of the ice may
+    /// be entered with the ice class, then they cost `penalty` times their cost.
+    pub fn weight(
+        &self,
+        edge_id: u32,
+        cost: u32,
+        month: u32,
+        ice_class: Option<IceClass>,
+        penalty: f64,
+    ) -> Option<u32> {
+        limits_weight(self.limits(edge_id), cost, month, ice_class, penalty)
+    }
+
+    /// Same as `weight` for an arc that is not an edge of the graph, e.g. the connection of a
+    /// `VirtualNode`.
+    pub fn arc_weight(
+        &self,
+        arc: &Arc,
+        cost: u32,
+        month: u32,
+        ice_class: Option<IceClass>,
+        penalty: f64,
+    ) -> Option<u32> {
+        let limits = self
+            .limits
+            .iter()
+            .filter(|limit| arc.collides(&limit.polygon));
+        limits_weight(limits, cost, month, ice_class, penalty)
+    }
+}
+
+/// Returns the cost in the month of an arc that collides with the limits, see `IceZones::weight`.
+fn limits_weight<'a>(
+    limits: impl Iterator<Item = &'a IceLimit>,
+    cost: u32,
+    month: u32,
+    ice_class: Option<IceClass>,
+    penalty: f64,
+) -> Option<u32> {
+    let mut active = limits.filter(|limit| limit.is_active(month)).peekable();
+    if active.peek().is_none() {
+        return Some(cost);
+    }
+    if !active.all(|limit| limit.admits(ice_class)) {
+        return None;
+    }
+    Some((cost as f64 * penalty).min(u32::MAX as f64) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use crate::{
+        geometry::{Point, Polygon},
+        routing::{
+            detour_area, detour_graph, month_of, random_graph, Dijkstra, DijkstraWorkspace,
+            IceClass, IceLimit, IceZones,
+        },
+    };
+
+    #[test]
+    fn months_of_unix_times() {
+        assert_eq!(month_of(0.0), 1);
+        assert_eq!(month_of(1_709_208_000.0), 2); // 2024-02-29 12:00
+        assert_eq!(month_of(1_719_791_999.0), 6); // 2024-06-30 23:59:59
+        assert_eq!(month_of(1_719_792_000.0), 7); // 2024-07-01
+        assert_eq!(month_of(1_735_689_599.0), 12); // 2024-12-31 23:59:59
+        assert_eq!(month_of(-1.0), 12); // 1969-12-31
+        assert_eq!(
+            "1a super".parse::<IceClass>().unwrap(),
+            IceClass::Baltic1ASuper
+        );
+        assert!(IceClass::Pc7 > IceClass::Baltic1A);
+    }
+
+    #[test]
+    fn ice_is_closed_in_winter_only() {
+        let graph = random_graph(1_000, 5_000);
+        let point = |lat, lon| Point::from_coordinate(lat, lon);
+        let winter_ice = IceLimit {
+            polygon: Polygon::new(vec![
+                point(10.05, 20.05),
+                point(10.05, 20.12),
+                point(10.12, 20.12),
+                point(10.12, 20.05),
+                point(10.05, 20.05),
+            ]),
+            months: 0b1110_0000_0111, // November to March
+            ice_class: Some(IceClass::Pc6),
+        };
+        let zones = IceZones::new(&graph, vec![winter_ice]);
+        for edge_id in 0..graph.edges.len() as u32 {
+            assert_eq!(
+                zones.arc_weight(&graph.edge_arc(edge_id), 1, 1, None, 1.0),
+                zones.weight(edge_id, 1, 1, None, 1.0)
+            );
+        }
+        let dijkstra = Dijkstra::new(&graph);
+        let without_ice = graph.filter_edges(|edge_id| zones.limits(edge_id).next().is_none());
+        let without_ice = Dijkstra::new(&without_ice);
+        let mut workspace = DijkstraWorkspace::new(graph.nodes.len());
+
+        let mut rng = StdRng::seed_from_u64(24);
+        for _ in 0..20 {
+            let from = rng.gen_range(0..1_000);
+            let to = rng.gen_range(0..1_000);
+            let mut cost = |month, ice_class| {
+                dijkstra.query_with_weights(&mut workspace, from, to, |edge_id, edge| {
+                    zones.weight(edge_id, edge.cost, month, ice_class, 1.0)
+                })
+            };
+            let open_water = dijkstra.dijkstra(from, to).1;
+            let winter = without_ice.dijkstra(from, to).1;
+            assert_eq!(cost(7, None), open_water);
+            assert_eq!(cost(1, None), winter);
+            assert_eq!(cost(1, Some(IceClass::Pc7)), winter);
+            assert_eq!(cost(1, Some(IceClass::Pc6)), open_water);
+        }
+    }
+
+    #[test]
+    fn ice_on_a_known_graph() {
+        let graph = detour_graph();
+        let winter_ice = IceLimit {
+            polygon: detour_area(),
+            months: 0b1110_0000_0111, // November to March
+            ice_class: Some(IceClass::Pc6),
+        };
+        let zones = IceZones::new(&graph, vec![winter_ice]);
+        let dijkstra = Dijkstra::new(&graph);
+        let mut workspace = DijkstraWorkspace::new(graph.nodes.len());
+
+        let mut route = |month, ice_class, penalty| {
+            dijkstra.query_with_weights(&mut workspace, 0, 2, |edge_id, edge| {
+                zones.weight(edge_id, edge.cost, month, ice_class, penalty)
+            });
+            workspace.route(&graph, 0, 2).unwrap().node_ids()
+        };
+        assert_eq!(route(7, None, 1.0), vec![0, 1, 2]);
+        assert_eq!(route(1, Some(IceClass::Pc7), 1.0), vec![0, 3, 2]);
+        assert_eq!(route(1, Some(IceClass::Pc5), 1.0), vec![0, 1, 2]);
+        // the slower passage through the ice takes longer than the detour
+        assert_eq!(route(1, Some(IceClass::Pc5), 2.0), vec![0, 3, 2]);
+    }
+}
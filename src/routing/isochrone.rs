@@ -0,0 +1,88 @@
+use std::f64::consts::PI;
+
+use crate::{geometry::Arc, spatial_partition::CellUnion};
+
+use super::{Dijkstra, DijkstraWorkspace, Graph};
+
+/// The area that can be reached from a node within a maximal cost, e.g. within N nautical miles
+/// from a port. The area is the union of the cells of the tiling at `depth` that are crossed by
+/// an edge between two reachable nodes.
+pub struct Isochrone {
+    pub start: u32,
+    pub max_cost: u32,
+    /// The reachable nodes, in order of their cost.
+    pub node_ids: Vec<u32>,
+    pub area: CellUnion,
+}
+
+impl Isochrone {
+    /// Searches the reachable nodes with the workspace, e.g. one reused for many queries.
+    pub fn new(
+        graph: &Graph,
+        workspace: &mut DijkstraWorkspace,
+        start: u32,
+        max_cost: u32,
+        depth: usize,
+    ) -> Isochrone {
+        let dijkstra = Dijkstra::new(graph);
+        let node_ids = dijkstra.one_to_all_within(workspace, start, max_cost);
+
+        // the edges are sampled densely enough to not skip a cell, so the area has no gaps
+        // between neighbouring nodes
+        let max_step = PI / 2.0 / 2f64.powi(depth as i32) / 2.0;
+        let point = |node_id: u32| graph.nodes[node_id as usize];
+        let mut points = Vec::new();
+        for &node_id in node_ids.iter() {
+            points.push(point(node_id));
+            for edge_id in graph.outgoing_edge_ids(node_id) {
+                let edge = &graph.edges[edge_id as usize];
+                if workspace.cost(edge.target_id) <= max_cost {
+                    Arc::new(&point(node_id), &point(edge.target_id))
+                        .sample(max_step)
+                        .for_each(|sample| points.push(sample));
+                }
+            }
+        }
+
+        Isochrone {
+            start,
+            max_cost,
+            node_ids,
+            area: CellUnion::new(&points, depth),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use crate::routing::{graph::random_graph, Dijkstra, DijkstraWorkspace, Isochrone};
+
+    #[test]
+    fn reaches_nodes_within_max_cost() {
+        let graph = random_graph(1_000, 5_000);
+        let dijkstra = Dijkstra::new(&graph);
+        let mut workspace = DijkstraWorkspace::new(graph.nodes.len());
+
+        let mut rng = StdRng::seed_from_u64(11);
+        for _ in 0..10 {
+            let start = rng.gen_range(0..1_000);
+            let max_cost = rng.gen_range(0..20_000);
+            let isochrone = Isochrone::new(&graph, &mut workspace, start, max_cost, 10);
+
+            let mut expected: Vec<u32> = dijkstra
+                .one_to_all(start)
+                .iter()
+                .enumerate()
+                .filter(|(_, &cost)| cost <= max_cost)
+                .map(|(node_id, _)| node_id as u32)
+                .collect();
+            let mut node_ids = isochrone.node_ids.clone();
+            node_ids.sort_unstable();
+            expected.sort_unstable();
+            assert_eq!(node_ids, expected);
+            assert!(!isochrone.area.outlines.is_empty());
+        }
+    }
+}
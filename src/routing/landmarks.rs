@@ -0,0 +1,227 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+use indicatif::ProgressIterator;
+use rayon::prelude::*;
+
+use crate::{text_reader::TextReader, Error, Result};
+
+use super::{Dijkstra, Graph, Heuristic};
+
+/// Precomputed costs between a few landmarks and all nodes. By the triangle inequality they give
+/// a lower bound of the cost between any two nodes (ALT). Unlike the great circle distance the
+/// bound stays valid and tight for any edge costs, as long as the costs used during a query are
+/// not lower than the costs the tables were computed with.
+#[derive(Clone)]
+pub struct Landmarks {
+    pub landmarks: Vec<u32>,
+    /// `from_landmark[i][node_id]` is the cost from the i-th landmark to the node.
+    pub from_landmark: Vec<Vec<u32>>,
+    /// `to_landmark[i][node_id]` is the cost from the node to the i-th landmark.
+    pub to_landmark: Vec<Vec<u32>>,
+}
+
+impl Landmarks {
+    /// Computes the cost tables for the given landmarks, e.g. nodes spread over the ocean basins
+    /// that were found via `Graph::nearest`.
+    pub fn new(graph: &Graph, landmarks: Vec<u32>) -> Landmarks {
+        println!("computing cost tables for {} landmarks", landmarks.len());
+        let dijkstra = Dijkstra::new(graph);
+        let (from_landmark, to_landmark) = landmarks
+            .par_iter()
+            .map(|&landmark| (dijkstra.one_to_all(landmark), dijkstra.all_to_one(landmark)))
+            .unzip();
+
+        Landmarks {
+            landmarks,
+            from_landmark,
+            to_landmark,
+        }
+    }
+
+    /// Selects landmarks by farthest point selection. Starting from the first node, the next
+    /// landmark is always the reachable node with the highest cost to the closest landmark
+    /// selected so far, so the same graph always gets the same landmarks. A graph without nodes
+    /// gets no landmarks.
+    pub fn farthest(graph: &Graph, num_landmarks: usize) -> Landmarks {
+        println!("selecting {} landmarks", num_landmarks);
+        if graph.nodes.is_empty() {
+            return Landmarks::new(graph, Vec::new());
+        }
+        let dijkstra = Dijkstra::new(graph);
+
+        let mut min_cost = dijkstra.one_to_all(0);
+        let mut landmarks = Vec::new();
+        for _ in (0..num_landmarks).progress() {
+            let Some((landmark, _)) = min_cost
+                .iter()
+                .enumerate()
+                .filter(|(_, &cost)| cost != u32::MAX)
+                .max_by_key(|(_, &cost)| cost)
+            else {
+                break;
+            };
+            let landmark = landmark as u32;
+            landmarks.push(landmark);
+
+            let cost = dijkstra.one_to_all(landmark);
+            min_cost
+                .iter_mut()
+                .zip(cost)
+                .for_each(|(min_cost, cost)| *min_cost = (*min_cost).min(cost));
+        }
+
+        Landmarks::new(graph, landmarks)
+    }
+
+    /// Reads the landmarks of the graph written by `to_file`. A file of a graph with another
+    /// number of nodes is an error.
+    pub fn from_file(path: &str, graph: &Graph) -> Result<Landmarks> {
+        let mut reader = TextReader::open(path)?;
+
+        let num_landmarks: usize = reader.next_value()?;
+        let num_nodes: usize = reader.next_value()?;
+        // a file without landmarks has no tables and thus no number of nodes
+        if num_landmarks > 0 && num_nodes != graph.nodes.len() {
+            return Err(Error::format(
+                path,
+                format!(
+                    "the landmarks are of a graph with {} nodes, not {}",
+                    num_nodes,
+                    graph.nodes.len()
+                ),
+            ));
+        }
+
+        let landmarks = (0..num_landmarks)
+            .map(|_| reader.next_value())
+            .collect::<Result<Vec<u32>>>()?;
+        if let Some(landmark) = landmarks
+            .iter()
+            .find(|&&landmark| landmark as usize >= num_nodes)
+        {
+            return Err(Error::format(
+                path,
+                format!("the landmark {} is not a node", landmark),
+            ));
+        }
+
+        let mut from_landmark = vec![Vec::with_capacity(num_nodes); num_landmarks];
+        let mut to_landmark = vec![Vec::with_capacity(num_nodes); num_landmarks];
+        for _ in (0..num_nodes).progress() {
+            let line = reader.next_line()?;
+            let mut values = line.split_whitespace();
+            for i in 0..num_landmarks {
+                from_landmark[i].push(reader.parse(values.next())?);
+                to_landmark[i].push(reader.parse(values.next())?);
+            }
+        }
+
+        Ok(Landmarks {
+            landmarks,
+            from_landmark,
+            to_landmark,
+        })
+    }
+
+    /// Writes the landmarks and, for every node, the costs from and to each landmark.
+    pub fn to_file(&self, path: &str) {
+        let num_nodes = self.from_landmark.first().map_or(0, |costs| costs.len());
+
+        let mut writer = BufWriter::new(File::create(path).unwrap());
+        writeln!(writer, "{}", self.landmarks.len()).unwrap();
+        writeln!(writer, "{}", num_nodes).unwrap();
+        for landmark in self.landmarks.iter() {
+            writeln!(writer, "{}", landmark).unwrap();
+        }
+
+        println!("writing costs of {} nodes to file", num_nodes);
+        for node_id in (0..num_nodes).progress() {
+            let line: Vec<String> = (0..self.landmarks.len())
+                .map(|i| {
+                    format!(
+                        "{} {}",
+                        self.from_landmark[i][node_id], self.to_landmark[i][node_id]
+                    )
+                })
+                .collect();
+            writeln!(writer, "{}", line.join(" ")).unwrap();
+        }
+        writer.flush().unwrap();
+    }
+}
+
+impl Heuristic for Landmarks {
+    fn lower_bound(&self, node_id: u32, target_id: u32) -> u32 {
+        let node_id = node_id as usize;
+        let target_id = target_id as usize;
+        (0..self.landmarks.len())
+            .map(|i| {
+                // d(l, t) - d(l, v) <= d(v, t)
+                let from = &self.from_landmark[i];
+                let forward = if from[node_id] != u32::MAX && from[target_id] != u32::MAX {
+                    from[target_id].saturating_sub(from[node_id])
+                } else {
+                    0
+                };
+                // d(v, l) - d(t, l) <= d(v, t)
+                let to = &self.to_landmark[i];
+                let backward = if to[node_id] != u32::MAX && to[target_id] != u32::MAX {
+                    to[node_id].saturating_sub(to[target_id])
+                } else {
+                    0
+                };
+                forward.max(backward)
+            })
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        routing::{
+            assert_same_cost_as_dijkstra, edge_case_graph, get_route, random_graph, AStar, Graph,
+            Landmarks,
+        },
+        TestFile,
+    };
+
+    #[test]
+    fn same_cost_as_dijkstra() {
+        for graph in [random_graph(1_000, 3_000), edge_case_graph()] {
+            let alt = AStar::new(&graph, Landmarks::farthest(&graph, 4));
+            assert_same_cost_as_dijkstra(&graph, |from, to| {
+                let (used_edges, cost) = alt.a_star(from, to);
+                (cost, get_route(&graph, from, to, used_edges))
+            });
+        }
+    }
+
+    #[test]
+    fn file_round_trip() {
+        let graph = random_graph(100, 300);
+        let landmarks = Landmarks::farthest(&graph, 3);
+        assert_eq!(
+            landmarks.landmarks,
+            Landmarks::farthest(&graph, 3).landmarks
+        );
+        let file = TestFile::empty("round_trip.landmarks");
+        landmarks.to_file(file.path());
+
+        let read = Landmarks::from_file(file.path(), &graph).unwrap();
+        assert_eq!(read.landmarks, landmarks.landmarks);
+        assert_eq!(read.from_landmark, landmarks.from_landmark);
+        assert_eq!(read.to_landmark, landmarks.to_landmark);
+        assert!(Landmarks::from_file(file.path(), &random_graph(101, 300)).is_err());
+    }
+
+    #[test]
+    fn empty_graph_has_no_landmarks() {
+        let graph = Graph::new(Vec::new(), Vec::new());
+        assert!(Landmarks::farthest(&graph, 3).landmarks.is_empty());
+    }
+}
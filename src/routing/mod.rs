@@ -1,7 +1,39 @@
+mod a_star;
+mod alternatives;
+mod avoid_areas;
+mod bidirectional_dijkstra;
 mod bucket_queue;
+mod canals;
+mod contraction_hierarchy;
+mod dijkstra_workspace;
 mod dijsktra;
+mod distance_table;
+mod emission_zones;
 mod graph;
+mod graph_array;
+mod ice_zones;
+mod isochrone;
+mod landmarks;
+mod sailing_time;
+mod stop_order;
+mod vessel;
 
+pub use a_star::*;
+pub use alternatives::*;
+pub use avoid_areas::*;
+pub use bidirectional_dijkstra::*;
 pub use bucket_queue::*;
+pub use canals::*;
+pub use contraction_hierarchy::*;
+pub use dijkstra_workspace::*;
 pub use dijsktra::*;
+pub use distance_table::*;
+pub use emission_zones::*;
 pub use graph::*;
+pub use graph_array::*;
+pub use ice_zones::*;
+pub use isochrone::*;
+pub use landmarks::*;
+pub use sailing_time::*;
+pub use stop_order::*;
+pub use vessel::*;
@@ -0,0 +1,220 @@
+use crate::geometry::{radians_to_meter, Arc, Point, VectorField, METERS_PER_NAUTICAL_MILE};
+
+use super::{Edge, Graph};
+
+/// The time a vessel takes to sail along arcs, from its speed through the water, the currents
+/// and the drift by the wind, e.g. to find the fastest instead of the shortest route. The vessel
+/// steers against the drift across the arc, so it keeps to the arc, and the drift along the arc
+/// adds to its speed.
+#[derive(Clone, Copy)]
+pub struct SailingTime<'a> {
+    /// Speed through the water in meters per second.
+    pub speed: f64,
+    pub currents: Option<&'a VectorField>,
+    pub wind: Option<&'a VectorField>,
+    /// The part of the wind speed the vessel drifts with, its leeway.
+    pub wind_drift: f64,
+}
+
+impl<'a> SailingTime<'a> {
+    pub fn new(knots: f64) -> SailingTime<'a> {
+        SailingTime {
+            speed: knots * METERS_PER_NAUTICAL_MILE / 3_600.0,
+            currents: None,
+            wind: None,
+            wind_drift: 0.03,
+        }
+    }
+
+    /// Returns the east and north component of the drift at the point and unix time in meters
+    /// per second.
+    pub fn drift(&self, point: &Point, time: f64) -> (f64, f64) {
+        let (mut east, mut north) = self
+            .currents
+            .map_or((0.0, 0.0), |currents| currents.at(point, time));
+        if let Some(wind) = self.wind {
+            let (wind_east, wind_north) = wind.at(point, time);
+            east += self.wind_drift * wind_east;
+            north += self.wind_drift * wind_north;
+        }
+        (east, north)
+    }
+
+    /// Returns the seconds to sail the arc when starting at the unix time, with the drift in the
+    /// middle of the arc. `None` if the drift is too strong to make way along the arc.
+    pub fn arc_seconds(&self, arc: &Arc, time: f64) -> Option<f64> {
+        let meters = radians_to_meter(arc.central_angle());
+        if meters == 0.0 {
+            return Some(0.0);
+        }
+        let (east, north) = self.drift(&arc.middle(), time);
+        let bearing = arc.initial_bearing();
+        let along = east * bearing.sin() + north * bearing.cos();
+        let across = east * bearing.cos() - north * bearing.sin();
+        if across.abs() >= self.speed {
+            return None;
+        }
+        let speed_over_ground = (self.speed.powi(2) - across.powi(2)).sqrt() + along;
+        (speed_over_ground > 0.0).then(|| meters / speed_over_ground)
+    }
+
+    /// Returns the whole seconds to sail the edge when starting at the unix time.
+    pub fn edge_seconds(&self, graph: &Graph, edge_id: u32, time: f64) -> Option<u32> {
+        let seconds = self.arc_seconds(&graph.edge_arc(edge_id), time)?;
+        Some(seconds.round().min(u32::MAX as f64) as u32)
+    }
+
+    /// Returns the weight for `Dijkstra::route_between_time_dependent`, which is the seconds to
+    /// sail an edge, given the seconds since the departure at the unix time `departure`.
+    pub fn weight(
+        &'a self,
+        graph: &'a Graph,
+        departure: f64,
+    ) -> impl Fn(u32, &Edge, u32) -> Option<u32> + 'a {
+        move |edge_id, _, seconds| self.edge_seconds(graph, edge_id, departure + seconds as f64)
+    }
+
+    /// Returns the unix time of arrival at each point when sailing along the points from the
+    /// departure, `None` if an arc cannot be sailed.
+    pub fn etas(&self, points: &[Point], departure: f64) -> Option<Vec<f64>> {
+        let mut time = departure;
+        let mut etas = Vec::with_capacity(points.len());
+        etas.extend(points.first().map(|_| time));
+        for points in points.windows(2) {
+            time += self.arc_seconds(&Arc::new(&points[0], &points[1]), time)?;
+            etas.push(time);
+        }
+        Some(etas)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use crate::{
+        geometry::{radians_to_meter, Arc, Point, VectorField},
+        routing::{detour_graph, random_graph, Dijkstra, DijkstraWorkspace, Route, SailingTime},
+        TestFile,
+    };
+
+    /// A current of 2 m/s to the east before the time 10000 and to the west after it, read from a
+    /// file of the name, which has to be unique among the tests.
+    fn turning_current(name: &str) -> VectorField {
+        let file = TestFile::new(
+            name,
+            "ncols 1\nnrows 1\nxllcorner 19\nyllcorner 9\ncellsize 3\n\
+             time 9000\n2 0\n\
+             time 11000\n-2 0\n",
+        );
+        VectorField::from_file(file.path()).unwrap()
+    }
+
+    #[test]
+    fn currents_speed_up_and_slow_down() {
+        let current = turning_current("speed_current.asc");
+        let mut sailing_time = SailingTime::new(10.0);
+        let arc = Arc::new(
+            &Point::from_coordinate(10.0, 20.0),
+            &Point::from_coordinate(10.0, 20.1),
+        );
+        let meters = radians_to_meter(arc.central_angle());
+        let is_close = |a: f64, b: f64| (a - b).abs() < 1.0;
+        let still_water = sailing_time.arc_seconds(&arc, 0.0).unwrap();
+        assert!(is_close(still_water, meters / sailing_time.speed));
+
+        sailing_time.currents = Some(&current);
+        let with_current = sailing_time.arc_seconds(&arc, 0.0).unwrap();
+        let against_current = sailing_time.arc_seconds(&arc, 20_000.0).unwrap();
+        assert!(is_close(with_current, meters / (sailing_time.speed + 2.0)));
+        assert!(is_close(
+            against_current,
+            meters / (sailing_time.speed - 2.0)
+        ));
+
+        // across the current the vessel steers against it
+        let north = Arc::new(
+            &Point::from_coordinate(10.0, 20.0),
+            &Point::from_coordinate(10.1, 20.0),
+        );
+        let meters = radians_to_meter(north.central_angle());
+        let speed = (sailing_time.speed.powi(2) - 4.0).sqrt();
+        assert!(is_close(
+            sailing_time.arc_seconds(&north, 0.0).unwrap(),
+            meters / speed
+        ));
+
+        // a vessel slower than the current cannot make way against it
+        let slow = SailingTime {
+            currents: Some(&current),
+            ..SailingTime::new(3.0)
+        };
+        assert!(slow.arc_seconds(&arc, 20_000.0).is_none());
+    }
+
+    #[test]
+    fn fastest_routes_are_never_slower_than_shortest() {
+        let graph = random_graph(1_000, 5_000);
+        let current = turning_current("fastest_current.asc");
+        let sailing_time = SailingTime {
+            currents: Some(&current),
+            ..SailingTime::new(10.0)
+        };
+        let dijkstra = Dijkstra::new(&graph);
+        let mut workspace = DijkstraWorkspace::new(graph.nodes.len());
+
+        let mut rng = StdRng::seed_from_u64(23);
+        for _ in 0..20 {
+            let from = rng.gen_range(0..1_000);
+            let to = rng.gen_range(0..1_000);
+            let departure = rng.gen_range(0.0..20_000.0);
+            let weight = sailing_time.weight(&graph, departure);
+            let Some(fastest) = dijkstra.route_between_time_dependent(
+                &mut workspace,
+                &[(from, 0)],
+                &[(to, 0)],
+                weight,
+            ) else {
+                continue;
+            };
+            dijkstra.query(&mut workspace, from, to);
+            let shortest = workspace.route(&graph, from, to).unwrap();
+
+            let arrival = |route: &Route| {
+                let points = graph.convert_path(&route.node_ids());
+                *sailing_time
+                    .etas(&points, departure)
+                    .unwrap()
+                    .last()
+                    .unwrap()
+            };
+            // each edge time is rounded to a second
+            let tolerance = fastest.edges.len() as f64;
+            assert!(arrival(&fastest) <= arrival(&shortest) + tolerance);
+        }
+    }
+
+    #[test]
+    fn fastest_route_on_a_known_graph() {
+        let graph = detour_graph();
+        let current = turning_current("known_graph_current.asc");
+        let sailing_time = SailingTime {
+            currents: Some(&current),
+            ..SailingTime::new(10.0)
+        };
+        let dijkstra = Dijkstra::new(&graph);
+        let mut workspace = DijkstraWorkspace::new(graph.nodes.len());
+
+        // the direct route is the fastest both with and against the current
+        for departure in [0.0, 20_000.0] {
+            let weight = sailing_time.weight(&graph, departure);
+            let fastest = dijkstra
+                .route_between_time_dependent(&mut workspace, &[(0, 0)], &[(2, 0)], weight)
+                .unwrap();
+            assert_eq!(fastest.node_ids(), vec![0, 1, 2]);
+            let points = graph.convert_path(&fastest.node_ids());
+            let arrival = sailing_time.etas(&points, departure).unwrap()[2];
+            assert!((departure + workspace.cost(2) as f64 - arrival).abs() <= 2.0);
+        }
+    }
+}
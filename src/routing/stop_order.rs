@@ -0,0 +1,216 @@
+/// Maximum number of improvement passes of the local search.
+const MAX_PASSES: usize = 1_000;
+
+/// Finds a good order to visit stops, e.g. the ports of a voyage, given the cost matrix between
+/// them, for example from a `DistanceTable`. `costs[i][j]` is the cost from stop `i` to stop `j`,
+/// `u32::MAX` if it is not reachable. If `start` or `end` is given, the order begins or ends with
+/// that stop, if both are the same stop it is visited at the beginning and the end.
+///
+/// The order is built with the nearest neighbour heuristic and then improved by 2-opt, which
+/// reverses parts of the order, and Or-opt, which moves up to three consecutive stops to another
+/// position, until no move makes the voyage cheaper.
+pub fn order_stops(costs: &[Vec<u32>], start: Option<usize>, end: Option<usize>) -> Vec<usize> {
+    let num_stops = costs.len();
+    if num_stops == 0 {
+        return Vec::new();
+    }
+
+    let mut order = match start {
+        Some(start) => nearest_neighbour(costs, start, end),
+        None => (0..num_stops)
+            .filter(|&first| Some(first) != end)
+            .map(|first| nearest_neighbour(costs, first, end))
+            .min_by_key(|order| order_cost(costs, order))
+            .unwrap_or_else(|| vec![end.unwrap()]),
+    };
+
+    // the first and last stop may not move if they are fixed
+    let first_movable = usize::from(start.is_some());
+    let last_movable = if end.is_some() {
+        order.len().saturating_sub(2)
+    } else {
+        order.len() - 1
+    };
+    for _ in 0..MAX_PASSES {
+        let improved = two_opt(costs, &mut order, first_movable, last_movable)
+            || or_opt(costs, &mut order, first_movable, last_movable);
+        if !improved {
+            break;
+        }
+    }
+
+    order
+}
+
+/// Returns the summed cost of visiting the stops in the given order.
+pub fn order_cost(costs: &[Vec<u32>], order: &[usize]) -> u64 {
+    order
+        .windows(2)
+        .map(|leg| costs[leg[0]][leg[1]] as u64)
+        .sum()
+}
+
+/// Starts at `first` and always continues with the cheapest stop not visited yet, `end` is
+/// visited last.
+fn nearest_neighbour(costs: &[Vec<u32>], first: usize, end: Option<usize>) -> Vec<usize> {
+    let mut is_visited = vec![false; costs.len()];
+    is_visited[first] = true;
+    if let Some(end) = end {
+        is_visited[end] = true;
+    }
+
+    let mut order = vec![first];
+    let mut current = first;
+    while let Some(next) = (0..costs.len())
+        .filter(|&stop| !is_visited[stop])
+        .min_by_key(|&stop| costs[current][stop])
+    {
+        is_visited[next] = true;
+        order.push(next);
+        current = next;
+    }
+    order.extend(end);
+    order
+}
+
+/// Returns the cost between two positions of the order, where `None` stands for before the first
+/// or after the last stop.
+fn cost(costs: &[Vec<u32>], from: Option<usize>, to: Option<usize>) -> u64 {
+    match (from, to) {
+        (Some(from), Some(to)) => costs[from][to] as u64,
+        _ => 0,
+    }
+}
+
+/// Applies the first reversal of a part of the order that lowers the cost. As the costs need not
+/// be symmetric, the reversed part is priced in reverse direction as well.
+fn two_opt(
+    costs: &[Vec<u32>],
+    order: &mut [usize],
+    first_movable: usize,
+    last_movable: usize,
+) -> bool {
+    let n = order.len();
+    // forward[k] and backward[k] are the costs of the legs before position k in both directions
+    let mut forward = vec![0; n];
+    let mut backward = vec![0; n];
+    for k in 1..n {
+        forward[k] = forward[k - 1] + costs[order[k - 1]][order[k]] as u64;
+        backward[k] = backward[k - 1] + costs[order[k]][order[k - 1]] as u64;
+    }
+
+    for i in first_movable..=last_movable {
+        for j in (i + 1)..=last_movable {
+            let before = i.checked_sub(1).map(|k| order[k]);
+            let after = order.get(j + 1).copied();
+            let old_cost = cost(costs, before, Some(order[i]))
+                + (forward[j] - forward[i])
+                + cost(costs, Some(order[j]), after);
+            let new_cost = cost(costs, before, Some(order[j]))
+                + (backward[j] - backward[i])
+                + cost(costs, Some(order[i]), after);
+            if new_cost < old_cost {
+                order[i..=j].reverse();
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// Applies the first move of one to three consecutive stops to another position that lowers the
+/// cost.
+fn or_opt(
+    costs: &[Vec<u32>],
+    order: &mut Vec<usize>,
+    first_movable: usize,
+    last_movable: usize,
+) -> bool {
+    let cost = |from: Option<usize>, to: Option<usize>| cost(costs, from, to) as i64;
+
+    for length in 1..=3 {
+        for i in first_movable..=last_movable {
+            let j = i + length - 1; // last position of the segment
+            if j > last_movable {
+                break;
+            }
+            let before = i.checked_sub(1).map(|k| order[k]);
+            let after = order.get(j + 1).copied();
+            let removal_delta =
+                cost(before, after) - cost(before, Some(order[i])) - cost(Some(order[j]), after);
+
+            // insert before position k of the order without the segment
+            let mut remaining = order.clone();
+            let segment: Vec<usize> = remaining.drain(i..=j).collect();
+            for k in first_movable..=(last_movable + 1 - length) {
+                if k == i {
+                    continue;
+                }
+                let before = k.checked_sub(1).map(|k| remaining[k]);
+                let after = remaining.get(k).copied();
+                let insertion_delta = cost(before, Some(segment[0]))
+                    + cost(Some(segment[length - 1]), after)
+                    - cost(before, after);
+                if removal_delta + insertion_delta < 0 {
+                    remaining.splice(k..k, segment);
+                    *order = remaining;
+                    return true;
+                }
+            }
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use crate::routing::{order_cost, order_stops};
+
+    fn random_costs(num_stops: usize) -> Vec<Vec<u32>> {
+        let mut rng = StdRng::seed_from_u64(9);
+        let points: Vec<(f64, f64)> = (0..num_stops)
+            .map(|_| (rng.gen_range(0.0..1000.0), rng.gen_range(0.0..1000.0)))
+            .collect();
+        points
+            .iter()
+            .map(|a| {
+                points
+                    .iter()
+                    .map(|b| ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt() as u32)
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn visits_every_stop_once() {
+        let costs = random_costs(50);
+        let mut order = order_stops(&costs, None, None);
+        order.sort_unstable();
+        assert_eq!(order, (0..50).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn keeps_fixed_start_and_end() {
+        let costs = random_costs(50);
+        let order = order_stops(&costs, Some(7), Some(3));
+        assert_eq!(order.len(), 50);
+        assert_eq!(order.first(), Some(&7));
+        assert_eq!(order.last(), Some(&3));
+
+        let order = order_stops(&costs, Some(7), Some(7));
+        assert_eq!(order.len(), 51);
+        assert_eq!(order.first(), Some(&7));
+        assert_eq!(order.last(), Some(&7));
+    }
+
+    #[test]
+    fn not_worse_than_identity() {
+        let costs = random_costs(50);
+        let identity: Vec<usize> = (0..50).collect();
+        let order = order_stops(&costs, Some(0), Some(49));
+        assert!(order_cost(&costs, &order) <= order_cost(&costs, &identity));
+    }
+}
@@ -0,0 +1,146 @@
+use super::{Edge, Graph};
+
+/// The limits for the vessels that use an edge, in meters: the least water depth and the least
+/// height above the water along the edge, e.g. under a bridge. Infinite where nothing is known.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[repr(C)]
+pub struct Clearance {
+    pub depth: f32,
+    pub height: f32,
+}
+
+// two f32 without padding, as in the clearance section of a graph file
+unsafe impl bytemuck::Zeroable for Clearance {}
+unsafe impl bytemuck::Pod for Clearance {}
+
+impl Clearance {
+    pub const UNLIMITED: Clearance = Clearance {
+        depth: f32::INFINITY,
+        height: f32::INFINITY,
+    };
+
+    pub fn is_limited(&self) -> bool {
+        *self != Clearance::UNLIMITED
+    }
+
+    /// Returns the tighter limits of both, e.g. of two parts of an edge.
+    pub fn min(&self, other: &Clearance) -> Clearance {
+        Clearance {
+            depth: self.depth.min(other.depth),
+            height: self.height.min(other.height),
+        }
+    }
+}
+
+/// The dimensions of a vessel that limit where it can go, in meters. The draft should include the
+/// under keel clearance the vessel keeps.
+#[derive(Clone, Copy, Debug)]
+pub struct Vessel {
+    pub draft: f32,
+    pub air_draft: f32,
+}
+
+impl Vessel {
+    pub fn fits(&self, clearance: &Clearance) -> bool {
+        self.draft < clearance.depth && self.air_draft < clearance.height
+    }
+
+    /// Returns the weight for `Dijkstra::query_with_weights` that keeps the edge costs, but blocks
+    /// the edges the vessel does not fit through. So one graph serves all vessels.
+    pub fn weight<'a>(&'a self, graph: &'a Graph) -> impl Fn(u32, &Edge) -> Option<u32> + 'a {
+        move |edge_id, edge| {
+            self.fits(&graph.clearances[edge_id as usize])
+                .then_some(edge.cost)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use crate::routing::{
+        detour_graph, random_graph, Clearance, Dijkstra, DijkstraWorkspace, Graph, Vessel,
+    };
+
+    #[test]
+    fn vessels_avoid_shallow_edges() {
+        let graph = random_graph(1_000, 5_000);
+        let mut rng = StdRng::seed_from_u64(20);
+        let clearances = (0..graph.edges.len())
+            .map(|_| Clearance {
+                depth: rng.gen_range(5.0..30.0),
+                height: if rng.gen_bool(0.1) {
+                    20.0
+                } else {
+                    f32::INFINITY
+                },
+            })
+            .collect();
+        let graph = Graph::with_clearances(graph.nodes, graph.edges.to_vec(), clearances);
+        let dijkstra = Dijkstra::new(&graph);
+        let mut workspace = DijkstraWorkspace::new(graph.nodes.len());
+
+        let vessel = Vessel {
+            draft: 12.0,
+            air_draft: 25.0,
+        };
+        // the edges the vessel fits through, as a graph of their own
+        let fitting_edges = graph
+            .edges
+            .iter()
+            .zip(graph.clearances.iter())
+            .filter(|(_, clearance)| vessel.fits(clearance))
+            .map(|(edge, _)| *edge)
+            .collect();
+        let fitting_graph = Graph::new(graph.nodes.clone(), fitting_edges);
+        let fitting_dijkstra = Dijkstra::new(&fitting_graph);
+
+        for _ in 0..20 {
+            let from = rng.gen_range(0..1_000);
+            let to = rng.gen_range(0..1_000);
+            let cost = dijkstra.query_with_weights(&mut workspace, from, to, vessel.weight(&graph));
+            assert_eq!(cost, fitting_dijkstra.dijkstra(from, to).1);
+            if let Some(route) = workspace.route(&graph, from, to) {
+                for edge in route.edges.iter() {
+                    let edge_id = graph
+                        .outgoing_edge_ids(edge.source_id)
+                        .find(|&id| graph.edges[id as usize].target_id == edge.target_id)
+                        .unwrap();
+                    assert!(vessel.fits(&graph.clearances[edge_id as usize]));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn deep_vessels_take_the_detour() {
+        let graph = detour_graph();
+        // the direct route through node 1 is 8 meters deep
+        let clearances = graph
+            .edges
+            .iter()
+            .map(|edge| match (edge.source_id, edge.target_id) {
+                (1, _) | (_, 1) => Clearance {
+                    depth: 8.0,
+                    height: f32::INFINITY,
+                },
+                _ => Clearance::UNLIMITED,
+            })
+            .collect();
+        let graph = Graph::with_clearances(graph.nodes, graph.edges.to_vec(), clearances);
+        let dijkstra = Dijkstra::new(&graph);
+        let mut workspace = DijkstraWorkspace::new(graph.nodes.len());
+
+        let mut route = |draft| {
+            let vessel = Vessel {
+                draft,
+                air_draft: 20.0,
+            };
+            dijkstra.query_with_weights(&mut workspace, 0, 2, vessel.weight(&graph));
+            workspace.route(&graph, 0, 2).unwrap().node_ids()
+        };
+        assert_eq!(route(5.0), vec![0, 1, 2]);
+        assert_eq!(route(12.0), vec![0, 3, 2]);
+    }
+}
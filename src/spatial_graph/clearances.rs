@@ -0,0 +1,93 @@
+use indicatif::ProgressIterator;
+use rayon::prelude::*;
+
+use crate::{
+    geometry::{radians_to_meter, Bathymetry, Bridge},
+    routing::{Clearance, Graph},
+};
+
+/// Returns the clearance of every edge of the graph, by edge id: the least depth of the
+/// bathymetry along the edge and the least clearance of the bridges the edge passes under.
+pub fn edge_clearances(
+    graph: &Graph,
+    bathymetry: Option<&Bathymetry>,
+    bridges: &[Bridge],
+) -> Vec<Clearance> {
+    let mut clearances = vec![Clearance::UNLIMITED; graph.edges.len()];
+    if let Some(bathymetry) = bathymetry {
+        println!("sampling depths along {} edges", graph.edges.len());
+        clearances
+            .par_iter_mut()
+            .enumerate()
+            .for_each(|(edge_id, clearance)| {
+                clearance.depth = bathymetry.min_depth(&graph.edge_arc(edge_id as u32))
+            });
+    }
+
+    if !bridges.is_empty() {
+        println!("placing {} bridges", bridges.len());
+        for bridge in bridges.iter().progress() {
+            for bridge_arc in bridge.arcs.iter() {
+                // an edge crossing the bridge has an end within this distance of its middle
+                let radius =
+                    radians_to_meter(bridge_arc.central_angle()) / 2.0 + graph.max_edge_length();
+                for (node_id, _) in graph.within(&bridge_arc.middle(), radius) {
+                    let edge_ids = graph
+                        .outgoing_edge_ids(node_id)
+                        .chain(graph.incoming_edge_ids(node_id).iter().copied());
+                    for edge_id in edge_ids {
+                        if graph.edge_arc(edge_id).intersects(bridge_arc) {
+                            let clearance = &mut clearances[edge_id as usize];
+                            clearance.height = clearance.height.min(bridge.clearance);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    clearances
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        geometry::{Arc, Bridge, Point},
+        routing::{Edge, Graph},
+        spatial_graph::edge_clearances,
+    };
+
+    #[test]
+    fn edges_under_bridges_are_limited() {
+        let nodes = vec![
+            Point::from_coordinate(10.0, 20.0),
+            Point::from_coordinate(10.2, 20.0),
+            Point::from_coordinate(10.0, 20.2),
+            Point::from_coordinate(10.2, 20.2),
+        ];
+        let edge = |source_id, target_id| Edge {
+            source_id,
+            target_id,
+            cost: 1,
+        };
+        let graph = Graph::new(nodes, vec![edge(0, 1), edge(1, 0), edge(2, 3), edge(0, 2)]);
+        // spans the edges between node 0 and 1 only
+        let bridge = Bridge {
+            arcs: vec![Arc::new(
+                &Point::from_coordinate(10.1, 19.9),
+                &Point::from_coordinate(10.1, 20.1),
+            )],
+            clearance: 30.0,
+        };
+
+        let clearances = edge_clearances(&graph, None, &[bridge]);
+        for (edge, clearance) in graph.edges.iter().zip(clearances.iter()) {
+            let expected = match (edge.source_id, edge.target_id) {
+                (0, 1) | (1, 0) => 30.0,
+                _ => f32::INFINITY,
+            };
+            assert_eq!(clearance.height, expected);
+            assert_eq!(clearance.depth, f32::INFINITY);
+        }
+    }
+}
@@ -7,38 +7,50 @@ use rayon::prelude::*;
 use crate::geometry::{
     meters_to_radians, radians_to_meter, Arc, CollisionDetection, Planet, Point, PointGenerator,
 };
+use crate::routing::Graph;
 use crate::spatial_partition::ConvecQuadrilateral;
 use crate::spatial_partition::{PointSpatialPartition, PolygonSpatialPartition};
 
-use super::Fmi;
+use super::GraphFile;
 
+/// Generates a network of `num_nodes` random points in the water north of `min_latitude`, e.g.
+/// -82 to leave out the Antarctic ice shelves, and writes it to the files.
 pub fn generate_network(
     num_nodes: u32,
+    min_latitude: f64,
     planet: &Planet,
     network_path: &str,
+    binary_network_path: Option<&str>,
     planet_path: &str,
     image_path: &str,
 ) {
     let start = Instant::now();
     let planet_grid = generate_planet_grid(planet);
-    let points = generate_points(num_nodes, &planet_grid);
+    let points = generate_points(num_nodes, min_latitude, &planet_grid);
 
     println!("took {:?}", start.elapsed());
     let point_grid = generate_point_grid(&points);
     let arcs = generate_arcs(&points, &point_grid, &planet_grid, 30_000.0);
 
-    let fmi = Fmi { points, arcs };
-    fmi.to_file(network_path);
-    let fmi_planet = fmi.to_planet();
+    let graph = Graph::from_arcs(points, &arcs);
+    graph.to_file(network_path);
+    if let Some(binary_network_path) = binary_network_path {
+        GraphFile::write(binary_network_path, &graph);
+    }
+    let graph_planet = graph.to_planet();
 
-    fmi_planet.to_image(image_path);
-    fmi_planet.to_geojson_file(planet_path);
+    graph_planet.to_image(image_path);
+    graph_planet.to_geojson_file(planet_path);
 }
 
-fn generate_points(how_many: u32, planet_grid: &PolygonSpatialPartition) -> Vec<Point> {
+fn generate_points(
+    how_many: u32,
+    min_latitude: f64,
+    planet_grid: &PolygonSpatialPartition,
+) -> Vec<Point> {
     println!("generating points");
     PointGenerator::new()
-        .filter(|point| point.latitude() >= -82.0)
+        .filter(|point| point.latitude() >= min_latitude)
         .filter(|point| !planet_grid.is_on_polygon(point))
         .take(how_many as usize)
         .progress_count(how_many as u64)
@@ -1,5 +1,9 @@
-mod graph;
+mod clearances;
+mod graph_file;
 mod graph_generator;
+mod virtual_node;
 
-pub use graph::*;
+pub use clearances::*;
+pub use graph_file::*;
 pub use graph_generator::*;
+pub use virtual_node::*;
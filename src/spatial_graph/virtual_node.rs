@@ -0,0 +1,61 @@
+use crate::{
+    geometry::{Arc, Point},
+    routing::Graph,
+    spatial_partition::PolygonSpatialPartition,
+    Error, Result,
+};
+
+/// A position that is not a node of the graph, e.g. a harbour mouth between the grid nodes. It
+/// is connected to nearby nodes through arcs, so routes can start and end exactly at the
+/// position instead of at the nearest node.
+pub struct VirtualNode {
+    pub point: Point,
+    /// The nodes the position is connected to and the cost of the connecting arc, in meters like
+    /// the edges of the graph.
+    pub connections: Vec<(u32, u32)>,
+}
+
+impl VirtualNode {
+    /// Connects the position to the `num_connections` nearest nodes. If a planet grid is given,
+    /// arcs that cross land are left out. If no arc is free of land, or no planet grid is given,
+    /// the position is connected to the nearest node only. A graph without nodes is an error.
+    pub fn new(
+        graph: &Graph,
+        point: Point,
+        planet_grid: Option<&PolygonSpatialPartition>,
+        num_connections: usize,
+    ) -> Result<VirtualNode> {
+        let mut connections = Vec::new();
+        if let Some(planet_grid) = planet_grid {
+            connections = graph
+                .k_nearest(&point, num_connections)
+                .into_iter()
+                .filter(|&(node_id, _)| {
+                    !planet_grid.check_collision(&Arc::new(&point, &graph.nodes[node_id as usize]))
+                })
+                .map(|(node_id, distance)| (node_id, distance as u32))
+                .collect();
+        }
+        if connections.is_empty() {
+            let Some(&(node_id, distance)) = graph.k_nearest(&point, 1).first() else {
+                return Err(Error::InvalidArgument("the graph has no nodes".to_string()));
+            };
+            connections.push((node_id, distance as u32));
+        }
+
+        Ok(VirtualNode { point, connections })
+    }
+
+    /// Returns the arc between the position and the node.
+    pub fn arc(&self, graph: &Graph, node_id: u32) -> Arc {
+        Arc::new(&self.point, &graph.nodes[node_id as usize])
+    }
+
+    /// Returns the cost of the arc between the position and the node, if they are connected.
+    pub fn cost(&self, node_id: u32) -> Option<u32> {
+        self.connections
+            .iter()
+            .find(|&&(connected_id, _)| connected_id == node_id)
+            .map(|&(_, cost)| cost)
+    }
+}
@@ -0,0 +1,176 @@
+use std::collections::{HashMap, HashSet};
+
+use geojson::{Feature, Geometry, Value};
+
+use crate::geometry::{Contains, Point, Polygon};
+
+use super::{tiling::Tiling, ConvecQuadrilateral};
+
+/// The union of all cells of the tiling at a fixed depth that contain at least one of the given
+/// points. The boundary of the union is traced into closed rings. Rings around covered cells are
+/// outlines, rings around uncovered cells inside an outline are holes, e.g. islands.
+///
+/// All cells at the same depth are split the same way, so two neighbouring cells share the exact
+/// same points along their common side. A side is part of the boundary if the neighbouring cell is
+/// not covered, i.e. if the side does not appear in opposite direction in another covered cell.
+pub struct CellUnion {
+    pub outlines: Vec<Polygon>,
+    pub holes: Vec<Polygon>,
+}
+
+impl CellUnion {
+    /// Creates the union of the cells at `depth` containing the points. The base tiling consists
+    /// of 12 cells, each level of depth splits a cell into four, so at depth 10 a cell is roughly
+    /// 10km wide.
+    pub fn new(points: &[Point], depth: usize) -> CellUnion {
+        let mut cells = Vec::new();
+        covered_cells(Tiling::base_tiling(), points.to_vec(), depth, &mut cells);
+
+        let mut sides: HashSet<(Point, Point)> = HashSet::new();
+        for cell in cells.iter() {
+            for side in cell.outline.windows(2) {
+                sides.insert((side[0], side[1]));
+            }
+        }
+        let mut next_points: HashMap<Point, Vec<Point>> = HashMap::new();
+        for &(from, to) in sides.iter() {
+            if !sides.contains(&(to, from)) {
+                next_points.entry(from).or_default().push(to);
+            }
+        }
+
+        let mut outlines = Vec::new();
+        let mut holes = Vec::new();
+        for ring in trace_rings(next_points) {
+            // the inside of a cell is on the right hand side of its outline, as seen by
+            // `Arc::is_on_righthand_side`, which is kept by the outlines and flipped by holes
+            let normal = ring
+                .windows(2)
+                .map(|side| side[0].n_vector().cross(side[1].n_vector()))
+                .sum::<nalgebra::Vector3<f64>>();
+            let center = ring.iter().map(|point| point.n_vector()).sum();
+            if normal.dot(&center) > 0.0 {
+                outlines.push(Polygon::new(ring));
+            } else {
+                holes.push(Polygon::new(ring));
+            }
+        }
+
+        CellUnion { outlines, holes }
+    }
+
+    /// Returns the union as a MultiPolygon feature, every hole is added to the outline that
+    /// contains it.
+    pub fn to_feature(&self) -> Feature {
+        let mut polygons: Vec<Vec<Vec<Vec<f64>>>> = self
+            .outlines
+            .iter()
+            .map(|outline| vec![outline.to_geojson_vec()])
+            .collect();
+        for hole in self.holes.iter() {
+            if let Some(i) = self
+                .outlines
+                .iter()
+                .position(|outline| outline.contains(&hole.outline[0]))
+            {
+                polygons[i].push(hole.to_geojson_vec());
+            }
+        }
+
+        Feature {
+            bbox: None,
+            geometry: Some(Geometry::new(Value::MultiPolygon(polygons))),
+            id: None,
+            properties: None,
+            foreign_members: None,
+        }
+    }
+}
+
+/// Distributes the points over the given cells, splits the cells down to `depth` and collects
+/// all cells at that depth that contain a point.
+fn covered_cells(
+    cells: Vec<ConvecQuadrilateral>,
+    points: Vec<Point>,
+    depth: usize,
+    covered: &mut Vec<ConvecQuadrilateral>,
+) {
+    let mut cell_points = vec![Vec::new(); cells.len()];
+    for point in points {
+        // points on the side between two cells are added to the first one
+        if let Some(i) = cells.iter().position(|cell| cell.contains(&point)) {
+            cell_points[i].push(point);
+        }
+    }
+
+    for (cell, points) in cells.into_iter().zip(cell_points) {
+        if points.is_empty() {
+            continue;
+        }
+        if depth == 0 {
+            covered.push(cell);
+        } else {
+            covered_cells(cell.split(), points, depth - 1, covered);
+        }
+    }
+}
+
+/// Follows the boundary sides until every side is used. Each ring is closed, its first and last
+/// point are the same.
+fn trace_rings(mut next_points: HashMap<Point, Vec<Point>>) -> Vec<Vec<Point>> {
+    let mut rings = Vec::new();
+    while let Some(&start) = next_points.keys().next() {
+        let mut ring = vec![start];
+        let mut current = start;
+        while let Some(next) = next_points.get_mut(&current).and_then(|next| next.pop()) {
+            if next_points[&current].is_empty() {
+                next_points.remove(&current);
+            }
+            ring.push(next);
+            current = next;
+            if current == start {
+                break;
+            }
+        }
+        rings.push(ring);
+    }
+    rings
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        geometry::{Contains, Point},
+        spatial_partition::CellUnion,
+    };
+
+    #[test]
+    fn single_cluster_has_one_outline() {
+        let points: Vec<Point> = (0..20)
+            .flat_map(|i| (0..20).map(move |j| (i, j)))
+            .map(|(i, j)| Point::from_coordinate(10.0 + i as f64 * 0.05, 20.0 + j as f64 * 0.05))
+            .collect();
+        let union = CellUnion::new(&points, 8);
+
+        assert_eq!(union.outlines.len(), 1);
+        assert!(union.holes.is_empty());
+        let outline = &union.outlines[0];
+        assert!(outline.outline.first() == outline.outline.last());
+        assert!(outline.contains(&Point::from_coordinate(10.5, 20.5)));
+        assert!(!outline.contains(&Point::from_coordinate(-10.5, -20.5)));
+    }
+
+    #[test]
+    fn ring_of_points_has_hole() {
+        let points: Vec<Point> = (0..40)
+            .flat_map(|i| (0..40).map(move |j| (i, j)))
+            .filter(|&(i, j)| !(10..30).contains(&i) || !(10..30).contains(&j))
+            .map(|(i, j)| Point::from_coordinate(10.0 + i as f64 * 0.05, 20.0 + j as f64 * 0.05))
+            .collect();
+        let union = CellUnion::new(&points, 8);
+
+        assert_eq!(union.outlines.len(), 1);
+        assert_eq!(union.holes.len(), 1);
+        assert!(union.holes[0].contains(&Point::from_coordinate(11.0, 21.0)));
+    }
+}
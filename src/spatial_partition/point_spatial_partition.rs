@@ -1,6 +1,8 @@
+use std::{cmp::Ordering, collections::BinaryHeap};
+
 use indicatif::ProgressIterator;
 
-use crate::geometry::{Collides, Contains, Point};
+use crate::geometry::{radians_to_meter, Arc, Collides, Contains, Point};
 
 use super::{tiling::Tiling, ConvecQuadrilateral};
 
@@ -14,7 +16,42 @@ pub struct PointSpatialPartition {
 #[derive(Clone)]
 enum PointNodeType {
     Internal(Vec<PointSpatialPartition>), // four children
-    Leaf(Vec<Point>),                     // a bucket of points
+    Leaf(Vec<(u32, Point)>),              // a bucket of points and their ids
+}
+
+/// An entry of the queue of `k_nearest`, ordered by its distance to the query point in reverse,
+/// so the `BinaryHeap` pops the closest entry first.
+enum Candidate<'a> {
+    Partition(f64, &'a PointSpatialPartition),
+    Point(f64, u32),
+}
+
+impl Candidate<'_> {
+    fn distance(&self) -> f64 {
+        match self {
+            Candidate::Partition(distance, _) | Candidate::Point(distance, _) => *distance,
+        }
+    }
+}
+
+impl PartialEq for Candidate<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate<'_> {}
+
+impl PartialOrd for Candidate<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.distance().total_cmp(&self.distance())
+    }
 }
 
 impl PointSpatialPartition {
@@ -48,7 +85,7 @@ impl PointSpatialPartition {
     }
 
     fn split(&mut self) {
-        let mut points: Vec<Point> = Vec::new();
+        let mut points: Vec<(u32, Point)> = Vec::new();
         if let PointNodeType::Leaf(old_points) = &self.node_type {
             points.extend(old_points);
         }
@@ -60,35 +97,48 @@ impl PointSpatialPartition {
                 .collect(),
         );
 
-        points.iter().for_each(|point| self.add_point(point));
+        points
+            .iter()
+            .for_each(|(id, point)| self.add_point(*id, point));
     }
 
+    /// Adds the points, the id of a point is its index in `points`.
     pub fn add_points(&mut self, points: &Vec<Point>) {
         println!("len is {}", points.len());
         points
             .iter()
+            .enumerate()
             .progress()
-            .for_each(|point| self.add_point(point));
+            .for_each(|(id, point)| self.add_point(id as u32, point));
     }
 
-    pub fn add_point(&mut self, point: &Point) {
+    pub fn add_point(&mut self, id: u32, point: &Point) {
         let mut internals = vec![self];
         while let Some(parent) = internals.pop() {
             // needs to be done before the match block, as the match block pushes a mutable
             // reference to internals.
             if let PointNodeType::Leaf(points) = &mut parent.node_type {
-                points.push(point.clone());
+                points.push((id, *point));
                 if points.len() >= parent.max_size {
                     parent.split();
                 }
                 break;
             } else if let PointNodeType::Internal(childs) = &mut parent.node_type {
-                for child in childs.iter_mut() {
-                    if child.boundary.contains(point) {
-                        internals.push(child);
-                        break;
-                    }
-                }
+                // a point on the side between two children is not contained in either due to
+                // rounding, so it is added to the child it is closest to
+                let index = childs
+                    .iter()
+                    .position(|child| child.boundary.contains(point))
+                    .unwrap_or_else(|| {
+                        (0..childs.len())
+                            .min_by(|&a, &b| {
+                                childs[a]
+                                    .distance_to(point)
+                                    .total_cmp(&childs[b].distance_to(point))
+                            })
+                            .unwrap()
+                    });
+                internals.push(&mut childs[index]);
             }
         }
     }
@@ -101,8 +151,8 @@ impl PointSpatialPartition {
                 points.extend(
                     leaf_points
                         .iter()
-                        .filter(|&point| polygon.contains(point))
-                        .cloned(),
+                        .filter(|(_, point)| polygon.contains(point))
+                        .map(|(_, point)| *point),
                 );
             } else if let PointNodeType::Internal(childs) = &parent.node_type {
                 internals.extend(childs.iter().filter(|q| q.boundary.collides(polygon)));
@@ -111,4 +161,112 @@ impl PointSpatialPartition {
 
         points
     }
+
+    /// Returns the ids of the `k` points closest to `point` and their distance in meters, the
+    /// closest first. Partitions are visited in order of their distance to the point, so only
+    /// the partitions around the point are searched.
+    pub fn k_nearest(&self, point: &Point, k: usize) -> Vec<(u32, f64)> {
+        let mut nearest = Vec::with_capacity(k);
+        let mut queue = BinaryHeap::new();
+        queue.push(Candidate::Partition(0.0, self));
+        while nearest.len() < k {
+            match queue.pop() {
+                None => break,
+                Some(Candidate::Point(distance, id)) => nearest.push((id, distance)),
+                Some(Candidate::Partition(_, partition)) => {
+                    match &partition.node_type {
+                        PointNodeType::Leaf(points) => {
+                            queue.extend(points.iter().map(|(id, other)| {
+                                Candidate::Point(distance_in_meters(point, other), *id)
+                            }));
+                        }
+                        PointNodeType::Internal(childs) => {
+                            queue.extend(childs.iter().map(|child| {
+                                Candidate::Partition(child.distance_to(point), child)
+                            }));
+                        }
+                    }
+                }
+            }
+        }
+        nearest
+    }
+
+    /// Returns the ids of all points within `radius` meters of `point` and their distance in
+    /// meters, in no particular order.
+    pub fn within(&self, point: &Point, radius: f64) -> Vec<(u32, f64)> {
+        let mut found = Vec::new();
+        let mut internals = vec![self];
+        while let Some(parent) = internals.pop() {
+            match &parent.node_type {
+                PointNodeType::Leaf(points) => found.extend(
+                    points
+                        .iter()
+                        .map(|(id, other)| (*id, distance_in_meters(point, other)))
+                        .filter(|&(_, distance)| distance <= radius),
+                ),
+                PointNodeType::Internal(childs) => internals.extend(
+                    childs
+                        .iter()
+                        .filter(|child| child.distance_to(point) <= radius),
+                ),
+            }
+        }
+        found
+    }
+
+    /// Returns a lower bound of the distance in meters between the point and any point in this
+    /// partition, which is 0 if the boundary contains the point. Must not be called on the root,
+    /// whose boundary is a placeholder.
+    fn distance_to(&self, point: &Point) -> f64 {
+        if self.boundary.contains(point) {
+            return 0.0;
+        }
+        let distance = self
+            .boundary
+            .outline
+            .windows(2)
+            .map(|side| Arc::new(&side[0], &side[1]).distance_to(point))
+            .fold(f64::INFINITY, f64::min);
+        radians_to_meter(distance)
+    }
+}
+
+fn distance_in_meters(from: &Point, to: &Point) -> f64 {
+    radians_to_meter(Arc::new(from, to).central_angle())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        geometry::{radians_to_meter, Arc, Point},
+        spatial_partition::PointSpatialPartition,
+    };
+
+    #[test]
+    fn k_nearest_and_within_match_linear_scan() {
+        let points: Vec<Point> = Point::random_generator().take(5_000).collect();
+        let mut point_grid = PointSpatialPartition::new_root(10);
+        point_grid.add_points(&points);
+
+        for query in Point::random_generator().take(50) {
+            let mut expected: Vec<(u32, f64)> = points
+                .iter()
+                .enumerate()
+                .map(|(id, point)| {
+                    let distance = radians_to_meter(Arc::new(&query, point).central_angle());
+                    (id as u32, distance)
+                })
+                .collect();
+            expected.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+            let nearest = point_grid.k_nearest(&query, 5);
+            assert_eq!(nearest, expected[..5].to_vec());
+
+            let radius = expected[20].1;
+            let mut within = point_grid.within(&query, radius);
+            within.sort_by(|a, b| a.1.total_cmp(&b.1));
+            assert_eq!(within, expected[..21].to_vec());
+        }
+    }
 }
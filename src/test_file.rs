@@ -0,0 +1,34 @@
+/// A file in the temporary directory for tests, removed when it is dropped, also if the test
+/// fails. The name is prefixed with the id of the process, so concurrent test runs do not collide,
+/// and must be unique among the tests.
+pub(crate) struct TestFile {
+    path: String,
+}
+
+impl TestFile {
+    /// Creates the file with the content.
+    pub(crate) fn new(name: &str, content: impl AsRef<[u8]>) -> TestFile {
+        let test_file = TestFile::empty(name);
+        std::fs::write(test_file.path(), content).unwrap();
+        test_file
+    }
+
+    /// Returns the file without creating it, e.g. for a function that writes it.
+    pub(crate) fn empty(name: &str) -> TestFile {
+        let path = std::env::temp_dir().join(format!("{}_{}", std::process::id(), name));
+        TestFile {
+            path: path.to_str().unwrap().to_string(),
+        }
+    }
+
+    pub(crate) fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+impl Drop for TestFile {
+    fn drop(&mut self) {
+        // the file does not exist if the test failed before writing it
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
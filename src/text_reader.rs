@@ -0,0 +1,66 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Lines},
+    str::FromStr,
+};
+
+use crate::{Error, Result};
+
+/// Reads a text file like `.fmi` line by line. Errors name the file and the line, so a broken
+/// file can be fixed without guessing.
+pub(crate) struct TextReader {
+    path: String,
+    lines: Lines<BufReader<File>>,
+    line_number: usize,
+}
+
+impl TextReader {
+    pub fn open(path: &str) -> Result<TextReader> {
+        let file = File::open(path).map_err(|error| Error::io(path, error))?;
+        Ok(TextReader {
+            path: path.to_string(),
+            lines: BufReader::new(file).lines(),
+            line_number: 0,
+        })
+    }
+
+    pub fn next_line(&mut self) -> Result<String> {
+        self.try_next_line()?.ok_or_else(|| {
+            Error::format(
+                &self.path,
+                format!("unexpected end of file at line {}", self.line_number),
+            )
+        })
+    }
+
+    /// Same as `next_line`, but returns `None` at the end of the file.
+    pub fn try_next_line(&mut self) -> Result<Option<String>> {
+        self.line_number += 1;
+        self.lines
+            .next()
+            .transpose()
+            .map_err(|error| Error::io(&self.path, error))
+    }
+
+    /// Parses the next line, which holds a single value like a count.
+    pub fn next_value<T: FromStr>(&mut self) -> Result<T> {
+        let line = self.next_line()?;
+        self.parse(Some(line.trim()))
+    }
+
+    /// Parses a value of the current line, `None` if the line has too few values.
+    pub fn parse<T: FromStr>(&self, value: Option<&str>) -> Result<T> {
+        let value = value.ok_or_else(|| {
+            Error::format(
+                &self.path,
+                format!("missing value in line {}", self.line_number),
+            )
+        })?;
+        value.parse().map_err(|_| {
+            Error::format(
+                &self.path,
+                format!("invalid value '{}' in line {}", value, self.line_number),
+            )
+        })
+    }
+}